@@ -0,0 +1,43 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Generate a test per fixture file, named after its year, day and file,
+/// e.g. "y2022_d15_example", for tests/fixtures.rs to include.
+fn main() {
+    println!("cargo:rerun-if-changed=fixtures");
+    let mut tests = String::new();
+    let mut paths = vec![];
+    for year in read_dir(Path::new("fixtures")) {
+        for day in read_dir(&year) {
+            println!("cargo:rerun-if-changed={}", day.display());
+            paths.extend(read_dir(&day).into_iter().filter(|path| path.is_file()));
+        }
+    }
+    paths.sort();
+    for path in paths {
+        let mut components = path.iter().rev().map(|c| c.to_string_lossy());
+        let name = components.next().unwrap();
+        let (day, year) = (components.next().unwrap(), components.next().unwrap());
+        let stem = name
+            .split('.')
+            .next()
+            .unwrap()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        tests.push_str(&format!(
+            "#[test]\nfn y{}_d{}_{}() {{\n    check(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/{}\"));\n}}\n\n",
+            year,
+            day,
+            stem,
+            path.display()
+        ));
+    }
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("fixtures.rs");
+    fs::write(out, tests).unwrap();
+}
+
+fn read_dir(dir: &Path) -> Vec<std::path::PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.map(|entry| entry.unwrap().path()).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,137 @@
+use crate::day::*;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+/// The expected answers, one line per day and part, e.g. "05 1 CMZ".  A
+/// grid answer is given as one line per row, all with the same day and part.
+/// Blank lines and lines starting with '#' are ignored.
+#[derive(Debug, Default)]
+pub struct Answers {
+    expected: HashMap<(String, usize), Vec<String>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    Missing,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "pass"),
+            Self::Fail { expected, actual } => write!(
+                f,
+                "FAIL: expected {}, got {}",
+                expected.join("\n"),
+                actual.join("\n")
+            ),
+            Self::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+impl Answers {
+    pub fn load(path: &str) -> BoxResult<Self> {
+        let file = fs::File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        Self::parse(&mut io::BufReader::new(file))
+    }
+
+    pub fn parse(input: &mut dyn io::Read) -> BoxResult<Self> {
+        io::BufReader::new(input).lines().enumerate().fold(
+            Ok(Self::default()),
+            |answers: BoxResult<Self>, (i, l)| {
+                let mut answers = answers?;
+                let l = l?;
+                let l = l.trim_end();
+                if l.is_empty() || l.starts_with('#') {
+                    return Ok(answers);
+                }
+                let malformed = || SimpleError::new(format!("malformed answer on line {}", i + 1));
+                let (tag, rest) = l.split_once(' ').ok_or_else(malformed)?;
+                let (part, answer) = rest.split_once(' ').ok_or_else(malformed)?;
+                let part = match part {
+                    "1" => 1,
+                    "2" => 2,
+                    _ => Err(malformed())?,
+                };
+                answers
+                    .expected
+                    .entry((tag.to_owned(), part))
+                    .or_default()
+                    .push(answer.to_owned());
+                Ok(answers)
+            },
+        )
+    }
+
+    pub fn check(&self, tag: &str, part: usize, answer: &Answer) -> Verdict {
+        match self.expected.get(&(tag.to_owned(), part)) {
+            None => Verdict::Missing,
+            Some(expected) => {
+                let actual = answer.lines();
+                if *expected == actual {
+                    Verdict::Pass
+                } else {
+                    Verdict::Fail {
+                        expected: expected.to_owned(),
+                        actual,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let answers = Answers::parse(
+            &mut "# day part answer
+01 1 24000
+05 2 MCD
+10 2 ##..
+10 2 #..#
+"
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            answers.check("01", 1, &Answer::Integer(24000)),
+            Verdict::Pass
+        );
+        assert_eq!(
+            answers.check("05", 2, &Answer::Text("MCD".to_owned())),
+            Verdict::Pass
+        );
+        assert_eq!(
+            answers.check(
+                "10",
+                2,
+                &Answer::Grid(vec!["##..".to_owned(), "#..#".to_owned()])
+            ),
+            Verdict::Pass
+        );
+        assert_eq!(
+            answers.check("01", 1, &Answer::Integer(45000)),
+            Verdict::Fail {
+                expected: vec!["24000".to_owned()],
+                actual: vec!["45000".to_owned()]
+            }
+        );
+        assert_eq!(
+            answers.check("01", 2, &Answer::Integer(45000)),
+            Verdict::Missing
+        );
+        assert!(Answers::parse(&mut "01 3 1".as_bytes()).is_err());
+    }
+}
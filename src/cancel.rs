@@ -0,0 +1,84 @@
+use crate::day::*;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Whether any part was ever cancelled, so that until one is, checking for
+/// cancellation costs no more than loading this.
+static ANY: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The token of the part being solved on this thread, if it can be
+    /// cancelled.
+    static TOKEN: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// A handle by which another thread gives up on a part, e.g. once it has run
+/// out of time.  The part notices when it next checks, and stops early.
+#[derive(Clone, Debug, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        ANY.store(true, Ordering::Relaxed);
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Run `f`, solving a part, so that cancelling `token` stops it at its next
+/// check.
+pub fn with_token<R, F: FnOnce() -> R>(token: &Token, f: F) -> R {
+    let previous = TOKEN.with(|current| current.replace(Some(token.to_owned())));
+    let rv = f();
+    TOKEN.with(|current| current.replace(previous));
+    rv
+}
+
+/// Whether the part being solved on this thread has been given up on.  The
+/// long-running searches and simulations check this every step or round,
+/// and return whatever they have, as their answer is no longer wanted.
+#[inline]
+pub fn cancelled() -> bool {
+    ANY.load(Ordering::Relaxed)
+        && TOKEN.with(|current| current.borrow().as_ref().is_some_and(Token::is_cancelled))
+}
+
+/// Fail as timed out if the part being solved on this thread has been given
+/// up on, for checking with `?`.
+pub fn check() -> BoxResult<()> {
+    if cancelled() {
+        Err(AocError::new(Reason::TimedOut))?
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let (token, other) = (Token::new(), Token::new());
+        assert!(!cancelled());
+        with_token(&token, || {
+            assert!(check().is_ok());
+            other.cancel();
+            assert!(!cancelled());
+            token.cancel();
+            assert!(cancelled());
+            let error = check().unwrap_err().downcast::<AocError>().unwrap();
+            assert_eq!(error.reason, Reason::TimedOut);
+            with_token(&other, || assert!(cancelled()));
+            with_token(&Token::new(), || assert!(!cancelled()));
+        });
+        assert!(!cancelled());
+    }
+}
@@ -0,0 +1,483 @@
+use adventofcode2022::day::*;
+use adventofcode2022::report::Format;
+use adventofcode2022::visual::Mode;
+use adventofcode2022::YEAR;
+use simple_error::SimpleError;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+pub const USAGE: &str = "Usage: adventofcode2022 [COMMAND] [OPTIONS] [DAYS]... [PREFIX]
+
+Commands:
+    run     Solve the selected days (the default)
+    bench   Solve the selected days repeatedly and report timing statistics
+    check   Solve the selected days and compare with the expected answers
+    list    List the days and their inputs
+    fetch   Download the inputs of the selected days that are not yet cached,
+            using the session token from the AOC_SESSION environment
+            variable or the \"session\" setting, from the site given by
+            AOC_BASE_URL or the \"base_url\" setting
+    generate
+            Write random inputs for the selected days where their inputs
+            would be read from, never overwriting an input, or to stdout
+            with the prefix \"-\"
+    crosscheck
+            Solve random inputs of the selected days both by their parts
+            and by slow reference solutions, where a day has them, and
+            show the smallest input found on which they disagree; the
+            days default to parameters small enough for the references,
+            e.g. 14 minutes for Day 16 and 16 for Day 19, which --param
+            overrides
+    help    Show this message
+
+Options:
+    -i, --input PREFIX  Prefix for the days' inputs, in a directory per
+                        year and named 01, 02, etc., optionally gzipped as
+                        01.gz, 02.gz, etc.; 2022 inputs may also be directly
+                        under PREFIX; a PREFIX containing {year} or {day} is
+                        a pattern instead, e.g. \"inputs/{year}/day{day}.txt\",
+                        and \"-\" reads the input from stdin
+                        (default \"input/\")
+        --inline TEXT   Use TEXT as the input
+    -y, --year YEAR     The year of days selected without one (default 2022)
+    -d, --days LIST     Select days, e.g. \"5\", \"5-12\" or \"1,3,20-25\", all
+                        days of a year, \"2020\", or days of a year, e.g.
+                        \"2020:8\"; days may also be given as arguments
+    -p, --part PART     Only solve part 1 or part 2
+    -t, --time          Report the time each part spends parsing its input,
+                        reading it included, and solving it
+    -f, --format FMT    Print results as \"text\" (the default), or as one
+                        \"json\" object per line or \"csv\" row per day and
+                        part, with the answer, its type, the (median) time
+                        of the part in seconds, parsing included, any
+                        error and, when checking, the verdict
+    -n, --repeat N      Number of times bench solves each part (default 10)
+    -j, --jobs N        Solve up to N days and parts concurrently (default 1)
+        --seed N        Seed of the generated inputs (default 0)
+        --size N        Size of the generated inputs, e.g. the number of
+                        lines or the side of a grid, depending on the day,
+                        or the largest size crosscheck tries (default 10)
+        --cases N       Number of inputs crosscheck tries (default 100)
+        --timeout SECS  Give up on a part that takes longer than SECS seconds
+                        per solve, reporting it as timed out, and carry on
+                        with the rest; the part is cancelled, which stops
+                        those of Days 12, 15-20, 23 and 24 soon after, while
+                        any other part keeps running in the background until
+                        it is done; the configuration may set it, too, as
+                        \"timeout = SECS\" (default no limit)
+    -a, --answers FILE  Expected answers for check, one \"DAY PART ANSWER\" per
+                        line (default \"answers\" in the directory of
+                        PREFIX, which must be given for a pattern or \"-\")
+    -c, --config FILE   Settings, one \"KEY = VALUE\" per line (default
+                        \"aoc.conf\", if it exists)
+        --param dayDAYS.NAME=VALUE
+                        Override a parameter of the given days, e.g.
+                        \"day15.row=10\" or \"day16.part2.minutes=20\"; the
+                        configuration may hold overrides, too, as
+                        \"day15.row = 10\"; list shows the parameters taken
+        --trace DAYS[=LEVEL]
+                        Show the diagnostics of the given days on stderr, at
+                        verbosity LEVEL from 1, a summary, to 3, dumps of the
+                        whole state (default 1), e.g. \"17\" or \"16,19=2\"
+        --visualize MODE
+                        Show the simulations of days 14, 17, 22, 23 and 24
+                        as they run, animated on stderr with \"terminal\", or
+                        as numbered PPM images in DIR with \"ppm:DIR\"
+                        (\"ppm\" writes them in \"frames\")
+    -h, --help          Show this message";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    Run,
+    Bench,
+    Check,
+    List,
+    Fetch,
+    Generate,
+    Crosscheck,
+    Help,
+}
+
+/// Selected days, each a day or a range of days of the default year, all
+/// days of a year, or a day or range of days of a year, e.g. "17", "5-12",
+/// "2020" or "2020:8".  Nothing selected means all days of the default year.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Days(Vec<(Option<usize>, Option<RangeInclusive<usize>>)>);
+
+impl Days {
+    pub fn contains(&self, year: usize, day: usize, default_year: usize) -> bool {
+        if self.0.is_empty() {
+            return year == default_year;
+        }
+        self.0.iter().any(|(y, range)| {
+            y.unwrap_or(default_year) == year && range.as_ref().is_none_or(|r| r.contains(&day))
+        })
+    }
+
+    fn parse(s: &str) -> BoxResult<Self> {
+        s.split(',')
+            .map(|item| {
+                let (year, range) = match item.split_once(':') {
+                    Some((year, range)) => (Some(Self::year(year)?), range),
+                    None => match item.trim().parse::<usize>() {
+                        Ok(year) if year > 25 => (Some(Self::year(item)?), ""),
+                        _ => (None, item),
+                    },
+                };
+                if range.is_empty() {
+                    return Ok((year, None));
+                }
+                let (first, last) = range.split_once('-').unwrap_or((range, range));
+                let (first, last) = (Self::day(first)?, Self::day(last)?);
+                if first > last {
+                    Err(SimpleError::new(format!("empty day range \"{}\"", range)))?
+                }
+                Ok((year, Some(first..=last)))
+            })
+            .collect::<BoxResult<_>>()
+            .map(Self)
+    }
+
+    fn year(s: &str) -> BoxResult<usize> {
+        match s.trim().parse::<usize>() {
+            Ok(year @ 2015..=9999) => Ok(year),
+            _ => Err(SimpleError::new(format!("invalid year \"{}\"", s)))?,
+        }
+    }
+
+    fn day(s: &str) -> BoxResult<usize> {
+        match s.trim().parse::<usize>() {
+            Ok(day @ 1..=25) => Ok(day),
+            _ => Err(SimpleError::new(format!("invalid day \"{}\"", s)))?,
+        }
+    }
+}
+
+/// A time limit in seconds, e.g. "10" or "0.5".
+pub fn timeout(s: &str) -> BoxResult<Duration> {
+    match s.trim().parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(SimpleError::new(format!("invalid timeout \"{}\"", s)))?,
+    }
+}
+
+/// An override of a parameter of the selected days, written
+/// "dayDAYS.NAME=VALUE", e.g. "day15.row=10", "day16.part2.minutes=20" or
+/// "day2020:8.NAME=VALUE".
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Override {
+    pub days: Days,
+    pub name: String,
+    pub value: String,
+}
+
+impl Override {
+    pub fn parse(key: &str, value: &str) -> BoxResult<Self> {
+        let invalid = || SimpleError::new(format!("invalid parameter \"{}\"", key));
+        let (days, name) = key
+            .trim()
+            .strip_prefix("day")
+            .and_then(|key| key.split_once('.'))
+            .ok_or_else(invalid)?;
+        if name.is_empty() {
+            Err(invalid())?
+        }
+        Ok(Self {
+            days: Days::parse(days)?,
+            name: name.to_owned(),
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    pub prefix: String,
+    pub inline: Option<String>,
+    pub year: usize,
+    pub days: Days,
+    pub part: Option<usize>,
+    pub time: bool,
+    pub format: Format,
+    pub repeat: usize,
+    pub answers: Option<String>,
+    pub jobs: usize,
+    pub timeout: Option<Duration>,
+    pub seed: u64,
+    pub size: usize,
+    pub cases: usize,
+    pub config: Option<String>,
+    pub params: Vec<Override>,
+    pub trace: Vec<(Days, u8)>,
+    pub visualize: Option<Mode>,
+}
+
+impl Args {
+    /// The file of expected answers: the one given, or "answers" in the
+    /// directory of the input prefix, which a pattern or stdin does not have.
+    pub fn answers_file(&self) -> BoxResult<String> {
+        if let Some(answers) = &self.answers {
+            return Ok(answers.to_owned());
+        }
+        if self.prefix == "-" || self.prefix.contains("{year}") || self.prefix.contains("{day}") {
+            Err(SimpleError::new(format!(
+                "--answers is needed with the input \"{}\"",
+                self.prefix
+            )))?
+        }
+        let dir = &self.prefix[..self.prefix.rfind('/').map_or(0, |i| i + 1)];
+        Ok(format!("{}answers", dir))
+    }
+
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> BoxResult<Self> {
+        let mut rv = Self {
+            command: Command::Run,
+            prefix: "input/".to_owned(),
+            inline: None,
+            year: YEAR,
+            days: Days(vec![]),
+            part: None,
+            time: false,
+            format: Format::Text,
+            repeat: 10,
+            answers: None,
+            jobs: 1,
+            timeout: None,
+            seed: 0,
+            size: 10,
+            cases: 100,
+            config: None,
+            params: vec![],
+            trace: vec![],
+            visualize: None,
+        };
+        let mut command = None;
+        let mut prefix = None;
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .ok_or_else(|| SimpleError::new(format!("option {} needs a value", option)))
+            };
+            match arg.as_str() {
+                "-i" | "--input" => rv.prefix = value(&arg)?,
+                "--inline" => rv.inline = Some(value(&arg)?),
+                "-y" | "--year" => rv.year = Days::year(&value(&arg)?)?,
+                "-d" | "--days" => rv.days.0.extend(Days::parse(&value(&arg)?)?.0),
+                "-p" | "--part" => {
+                    rv.part = Some(match value(&arg)?.as_str() {
+                        "1" => 1,
+                        "2" => 2,
+                        part => Err(SimpleError::new(format!("invalid part \"{}\"", part)))?,
+                    })
+                }
+                "-t" | "--time" => rv.time = true,
+                "-f" | "--format" => rv.format = value(&arg)?.parse()?,
+                "-n" | "--repeat" => {
+                    let n = value(&arg)?;
+                    rv.repeat = match n.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => Err(SimpleError::new(format!("invalid repeat count \"{}\"", n)))?,
+                    }
+                }
+                "-j" | "--jobs" => {
+                    let n = value(&arg)?;
+                    rv.jobs = match n.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => Err(SimpleError::new(format!("invalid job count \"{}\"", n)))?,
+                    }
+                }
+                "--seed" => {
+                    let n = value(&arg)?;
+                    rv.seed = n
+                        .parse()
+                        .map_err(|_| SimpleError::new(format!("invalid seed \"{}\"", n)))?
+                }
+                "--size" => {
+                    let n = value(&arg)?;
+                    rv.size = match n.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => Err(SimpleError::new(format!("invalid size \"{}\"", n)))?,
+                    }
+                }
+                "--cases" => {
+                    let n = value(&arg)?;
+                    rv.cases = match n.parse() {
+                        Ok(n) if n > 0 => n,
+                        _ => Err(SimpleError::new(format!("invalid case count \"{}\"", n)))?,
+                    }
+                }
+                "--timeout" => rv.timeout = Some(timeout(&value(&arg)?)?),
+                "-a" | "--answers" => rv.answers = Some(value(&arg)?),
+                "-c" | "--config" => rv.config = Some(value(&arg)?),
+                "--param" => {
+                    let param = value(&arg)?;
+                    let (key, value) = param.split_once('=').ok_or_else(|| {
+                        SimpleError::new(format!("parameter \"{}\" needs a value", param))
+                    })?;
+                    rv.params.push(Override::parse(key, value)?);
+                }
+                "--trace" => {
+                    let trace = value(&arg)?;
+                    let (days, level) = trace.split_once('=').unwrap_or((&trace, "1"));
+                    let level = match level.parse() {
+                        Ok(level) if level > 0 => level,
+                        _ => Err(SimpleError::new(format!(
+                            "invalid trace level \"{}\"",
+                            level
+                        )))?,
+                    };
+                    rv.trace.push((Days::parse(days)?, level));
+                }
+                "--visualize" => rv.visualize = Some(value(&arg)?.parse()?),
+                "-h" | "--help" => rv.command = Command::Help,
+                s if s.starts_with('-') && s != "-" => {
+                    Err(SimpleError::new(format!("unknown option \"{}\"", s)))?
+                }
+                "run" if command.is_none() && prefix.is_none() => command = Some(Command::Run),
+                "bench" if command.is_none() && prefix.is_none() => command = Some(Command::Bench),
+                "check" if command.is_none() && prefix.is_none() => command = Some(Command::Check),
+                "list" if command.is_none() && prefix.is_none() => command = Some(Command::List),
+                "fetch" if command.is_none() && prefix.is_none() => command = Some(Command::Fetch),
+                "generate" if command.is_none() && prefix.is_none() => {
+                    command = Some(Command::Generate)
+                }
+                "crosscheck" if command.is_none() && prefix.is_none() => {
+                    command = Some(Command::Crosscheck)
+                }
+                "help" if command.is_none() && prefix.is_none() => command = Some(Command::Help),
+                s if s.starts_with(|c: char| c.is_ascii_digit()) => {
+                    rv.days.0.extend(Days::parse(s)?.0)
+                }
+                _ if prefix.is_none() => prefix = Some(arg),
+                _ => Err(SimpleError::new(format!("unexpected argument \"{}\"", arg)))?,
+            }
+        }
+        if rv.command != Command::Help {
+            rv.command = command.unwrap_or(Command::Run);
+        }
+        if let Some(prefix) = prefix {
+            rv.prefix = prefix;
+        }
+        Ok(rv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> BoxResult<Args> {
+        Args::parse(s.split_whitespace().map(|s| s.to_owned()))
+    }
+
+    #[test]
+    fn commands() {
+        let args = parse("input/").unwrap();
+        assert_eq!(args.command, Command::Run);
+        assert_eq!(args.prefix, "input/");
+        let args = parse("list --input data/").unwrap();
+        assert_eq!(args.command, Command::List);
+        assert_eq!(args.prefix, "data/");
+        let args = parse("run - --inline 1").unwrap();
+        assert_eq!(args.prefix, "-");
+        assert_eq!(args.inline, Some("1".to_owned()));
+        assert_eq!(parse("run -h").unwrap().command, Command::Help);
+        assert!(parse("run input/ extra").is_err());
+        assert!(parse("--bogus").is_err());
+        assert!(parse("--days").is_err());
+        let args = parse("bench -n 3").unwrap();
+        assert_eq!(args.command, Command::Bench);
+        assert_eq!(args.repeat, 3);
+        assert!(parse("bench -n 0").is_err());
+        assert_eq!(parse("run -j 4").unwrap().jobs, 4);
+        assert_eq!(parse("run -f json").unwrap().format, Format::Json);
+        assert!(parse("run --format xml").is_err());
+        assert!(parse("run -j x").is_err());
+        let args = parse("run --timeout 0.5").unwrap();
+        assert_eq!(args.timeout, Some(Duration::from_millis(500)));
+        assert!(parse("run --timeout 0").is_err());
+        assert!(parse("run --timeout soon").is_err());
+        let args = parse("check -a expected.txt input/").unwrap();
+        assert_eq!(args.command, Command::Check);
+        assert_eq!(args.answers, Some("expected.txt".to_owned()));
+        let args = parse("fetch -c my.conf --days 5").unwrap();
+        assert_eq!(args.command, Command::Fetch);
+        assert_eq!(args.config, Some("my.conf".to_owned()));
+        let args = parse("generate --seed 7 --size 40 16 gen/").unwrap();
+        assert_eq!(args.command, Command::Generate);
+        assert_eq!(
+            (args.seed, args.size, args.prefix.as_str()),
+            (7, 40, "gen/")
+        );
+        assert!(parse("generate --size 0").is_err());
+        assert!(parse("generate --seed -1").is_err());
+        let args = parse("crosscheck --cases 500 --size 6 16,19").unwrap();
+        assert_eq!(args.command, Command::Crosscheck);
+        assert_eq!((args.cases, args.size), (500, 6));
+        assert!(parse("crosscheck --cases 0").is_err());
+        let args = parse("run --param day15.row=10 --param day2020:8.part2.x=-1").unwrap();
+        assert_eq!(args.params.len(), 2);
+        assert!(args.params[0].days.contains(YEAR, 15, YEAR));
+        assert_eq!(args.params[0].name, "row");
+        assert_eq!(args.params[0].value, "10");
+        assert!(args.params[1].days.contains(2020, 8, YEAR));
+        assert_eq!(args.params[1].name, "part2.x");
+        assert!(parse("run --param day15.row").is_err());
+        assert!(parse("run --param 15.row=10").is_err());
+        assert!(parse("run --param day15=10").is_err());
+        let args = parse("run 17 --visualize terminal").unwrap();
+        assert_eq!(args.visualize, Some(Mode::Terminal));
+        assert!(parse("run --visualize gif").is_err());
+    }
+
+    #[test]
+    fn days() {
+        let args = parse("run --days 1,5-12 --part 2").unwrap();
+        let contains = |day| args.days.contains(YEAR, day, args.year);
+        assert!(contains(1));
+        assert!(!contains(2));
+        assert!(contains(12));
+        assert!(!contains(13));
+        assert_eq!(args.part, Some(2));
+        let args = parse("run").unwrap();
+        assert!(args.days.contains(YEAR, 25, args.year));
+        assert!(!args.days.contains(2020, 8, args.year));
+        let args = parse("run 2022:17 2020:8 input/").unwrap();
+        assert_eq!(args.prefix, "input/");
+        assert!(args.days.contains(2022, 17, args.year));
+        assert!(args.days.contains(2020, 8, args.year));
+        assert!(!args.days.contains(2020, 9, args.year));
+        let args = parse("run --year 2020 -d 8,2022").unwrap();
+        assert!(args.days.contains(2020, 8, args.year));
+        assert!(args.days.contains(2022, 1, args.year));
+        assert!(parse("run --days 12-5").is_err());
+        assert!(parse("run --days 26").is_err());
+        assert!(parse("run --year 22").is_err());
+        assert!(parse("run --part 3").is_err());
+        let args = parse("run --trace 17 --trace 16,2020:8=3").unwrap();
+        let level = |year, day| {
+            args.trace
+                .iter()
+                .filter(|(days, _)| days.contains(year, day, args.year))
+                .map(|&(_, level)| level)
+                .max()
+        };
+        assert_eq!(level(YEAR, 17), Some(1));
+        assert_eq!(level(2020, 8), Some(3));
+        assert_eq!(level(YEAR, 8), None);
+        assert!(parse("run --trace 17=0").is_err());
+        assert!(parse("run --trace 17=x").is_err());
+    }
+
+    #[test]
+    fn answers() {
+        let answers = |s: &str| parse(s).unwrap().answers_file();
+        assert_eq!(answers("check").unwrap(), "input/answers");
+        assert_eq!(answers("check data/2022/day").unwrap(), "data/2022/answers");
+        assert_eq!(answers("check day").unwrap(), "answers");
+        assert_eq!(answers("check - -a expected").unwrap(), "expected");
+        assert!(answers("check -").is_err());
+        assert!(answers("check inputs/{year}/day{day}.txt").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::day::*;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+/// Settings from a configuration file, one "KEY = VALUE" per line.  Blank
+/// lines and lines starting with '#' are ignored.
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> BoxResult<Self> {
+        let file = fs::File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        Self::parse(&mut io::BufReader::new(file))
+    }
+
+    pub fn parse(input: &mut dyn io::Read) -> BoxResult<Self> {
+        io::BufReader::new(input).lines().enumerate().fold(
+            Ok(Self::default()),
+            |config: BoxResult<Self>, (i, l)| {
+                let mut config = config?;
+                let l = l?;
+                let l = l.trim();
+                if l.is_empty() || l.starts_with('#') {
+                    return Ok(config);
+                }
+                let (key, value) = l.split_once('=').ok_or_else(|| {
+                    SimpleError::new(format!("malformed setting on line {}", i + 1))
+                })?;
+                config
+                    .values
+                    .insert(key.trim().to_owned(), value.trim().to_owned());
+                Ok(config)
+            },
+        )
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+
+    /// A setting, taken from the environment variable `var` if set, else
+    /// from the configuration.
+    pub fn setting(&self, key: &str, var: &str) -> Option<String> {
+        env::var(var)
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or_else(|| self.get(key).map(|s| s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let config = Config::parse(
+            &mut "# fetching
+session = 53616c7465645f5f
+base_url=http://localhost:8080
+"
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(config.get("session"), Some("53616c7465645f5f"));
+        assert_eq!(config.get("base_url"), Some("http://localhost:8080"));
+        assert_eq!(config.get("missing"), None);
+        assert_eq!(
+            config.setting("session", "AOC_TEST_UNSET_VARIABLE"),
+            Some("53616c7465645f5f".to_owned())
+        );
+        assert!(Config::parse(&mut "session".as_bytes()).is_err());
+    }
+}
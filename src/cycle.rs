@@ -0,0 +1,95 @@
+use crate::cancel;
+use crate::day::*;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A repetition in a simulation: after `start + period` steps it is back in
+/// the state it was in after `start` steps.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// The value after `n` steps, given the values after each of at least
+    /// the first `start + period` steps, assuming that the value grows by the
+    /// same amount every period.
+    pub fn value_at(&self, values: &[i64], n: usize) -> i64 {
+        if let Some(&value) = values.get(n) {
+            return value;
+        }
+        let (cycles, offset) = (
+            (n - self.start) / self.period,
+            (n - self.start) % self.period,
+        );
+        let growth = values[self.start + self.period] - values[self.start];
+        values[self.start + offset] + cycles as i64 * growth
+    }
+}
+
+/// The value measured on a simulation after `n` steps.  The simulation is
+/// stepped until it is found in a state it has been in before, as told by
+/// `fingerprint`, and the value then extrapolated from those measured so
+/// far, so that `n` may be far too large to simulate.
+pub fn extrapolate<S, F, K, P, M>(
+    state: &mut S,
+    n: usize,
+    mut step: F,
+    mut fingerprint: K,
+    mut measure: M,
+) -> BoxResult<i64>
+where
+    F: FnMut(&mut S) -> BoxResult<()>,
+    K: FnMut(&S) -> P,
+    P: Eq + Hash,
+    M: FnMut(&S) -> i64,
+{
+    let mut seen = HashMap::new();
+    let mut values = vec![];
+    for i in 0..n {
+        values.push(measure(state));
+        if let Some(start) = seen.insert(fingerprint(state), i) {
+            let cycle = Cycle {
+                start,
+                period: i - start,
+            };
+            trace!(1, "{:?}", cycle);
+            return Ok(cycle.value_at(&values, n));
+        }
+        step(state)?;
+        cancel::check()?;
+    }
+    Ok(measure(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrapolation() {
+        // A counter going from 0 to 9, then round from 3 to 9 again, summed.
+        let step = |(i, sum): &mut (usize, i64)| {
+            *i = if *i < 9 { *i + 1 } else { 3 };
+            *sum += *i as i64;
+            Ok(())
+        };
+        let simulated = |n| {
+            let mut state = (0, 0);
+            for _ in 0..n {
+                step(&mut state).unwrap();
+            }
+            state.1
+        };
+        for n in [0, 2, 5, 10, 11, 100, 1001] {
+            let value = extrapolate(&mut (0, 0), n, step, |&(i, _)| i, |&(_, sum)| sum);
+            assert_eq!(value.unwrap(), simulated(n), "n = {}", n);
+        }
+        let cycle = Cycle {
+            start: 1,
+            period: 2,
+        };
+        assert_eq!(cycle.value_at(&[0, 5, 6, 8], 7), 14);
+    }
+}
@@ -1,37 +1,440 @@
 pub use itertools::Itertools;
+use std::collections::BTreeMap;
 pub use std::error;
 use std::fmt;
 pub use std::io;
 pub use std::io::BufRead;
 
+use crate::random::Rng;
+pub use crate::trace;
+use simple_error::SimpleError;
+
 pub type BoxResult<T> = Result<T, Box<dyn error::Error>>;
 
-#[derive(Debug, Clone)]
-pub struct AocError;
+/// Why a day failed to produce an answer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Reason {
+    #[default]
+    Unexpected,
+    Parse,
+    MissingField,
+    OutOfBounds,
+    NoSolution,
+    Panic,
+    TimedOut,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Unexpected => "unexpected error",
+                Self::Parse => "parse failure",
+                Self::MissingField => "missing field",
+                Self::OutOfBounds => "out of bounds",
+                Self::NoSolution => "no solution found",
+                Self::Panic => "panicked",
+                Self::TimedOut => "timed out",
+            }
+        )
+    }
+}
+
+/// An error with as much context as the failing site could provide: the
+/// day, where in the input it happened, the offending token, and the error
+/// it was caused by, if any.
+#[derive(Debug, Default)]
+pub struct AocError {
+    pub reason: Reason,
+    pub day: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub token: Option<String>,
+    pub source: Option<Box<dyn error::Error>>,
+}
+
+impl AocError {
+    pub fn new(reason: Reason) -> Self {
+        Self {
+            reason,
+            ..Default::default()
+        }
+    }
+
+    pub fn day(mut self, day: &str) -> Self {
+        self.day = Some(day.to_owned());
+        self
+    }
+
+    pub fn line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    pub fn token<T: fmt::Display + ?Sized>(mut self, token: &T) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Turn any error into an `AocError` so that context can be added to it.
+    /// Errors from other sources are kept as the cause.
+    fn wrap(e: Box<dyn error::Error>) -> Box<Self> {
+        e.downcast::<Self>().unwrap_or_else(|e| {
+            let reason =
+                if e.is::<std::num::ParseIntError>() || e.is::<std::string::FromUtf8Error>() {
+                    Reason::Parse
+                } else {
+                    Reason::Unexpected
+                };
+            Box::new(Self {
+                reason,
+                source: Some(e),
+                ..Default::default()
+            })
+        })
+    }
+}
 
 impl fmt::Display for AocError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "unexpected error")
+        write!(f, "{}", self.reason)?;
+        if let Some(day) = &self.day {
+            write!(f, " in day {}", day)?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " at line {}, column {}", line, column)?,
+            (Some(line), None) => write!(f, " at line {}", line)?,
+            (None, Some(column)) => write!(f, " at column {}", column)?,
+            (None, None) => {}
+        }
+        if let Some(token) = &self.token {
+            write!(f, " near {:?}", token)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for AocError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source.as_deref()
     }
 }
 
-impl error::Error for AocError {}
+/// Cheaply attach context to a failure on its way up, e.g.
+/// `tokens.next().with_reason(Reason::MissingField).at_line(i + 1)?`.
+pub trait Context<T> {
+    fn with_context<F: FnOnce(AocError) -> AocError>(self, f: F) -> BoxResult<T>;
+
+    fn with_reason(self, reason: Reason) -> BoxResult<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|e| AocError { reason, ..e })
+    }
+
+    fn with_token<S: fmt::Display + ?Sized>(self, token: &S) -> BoxResult<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|e| e.token(token))
+    }
+
+    fn at_line(self, line: usize) -> BoxResult<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|e| e.line(line))
+    }
+
+    fn at_column(self, column: usize) -> BoxResult<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|e| e.column(column))
+    }
+
+    fn in_day(self, day: &str) -> BoxResult<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|e| e.day(day))
+    }
+}
+
+impl<T, E: Into<Box<dyn error::Error>>> Context<T> for Result<T, E> {
+    fn with_context<F: FnOnce(AocError) -> AocError>(self, f: F) -> BoxResult<T> {
+        self.map_err(|e| Box::new(f(*AocError::wrap(e.into()))) as Box<dyn error::Error>)
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn with_context<F: FnOnce(AocError) -> AocError>(self, f: F) -> BoxResult<T> {
+        self.ok_or_else(|| f(AocError::default()).into())
+    }
+}
+
+/// The outcome of solving one part of a day.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Integer(i64),
+    Text(String),
+    Grid(Vec<String>),
+    Error {
+        message: String,
+        causes: Vec<String>,
+    },
+    Unimplemented,
+}
+
+impl Answer {
+    /// The kind of answer, as named in machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Integer(_) => "integer",
+            Self::Text(_) => "text",
+            Self::Grid(_) => "grid",
+            Self::Error { .. } => "error",
+            Self::Unimplemented => "unimplemented",
+        }
+    }
+
+    /// The answer as text, one line per grid row.
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            Self::Grid(rows) => rows.to_owned(),
+            _ => vec![self.to_string()],
+        }
+    }
+
+    pub fn error(e: &dyn error::Error) -> Self {
+        let mut causes = vec![];
+        let mut source = e.source();
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        Self::Error {
+            message: e.to_string(),
+            causes,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Integer(n) => write!(f, "{}", n),
+            Self::Text(s) => write!(f, "{}", s),
+            Self::Grid(rows) => write!(f, "{}", rows.join("\n")),
+            Self::Error { message, causes } => {
+                write!(f, "error: {}", message)?;
+                causes.iter().try_for_each(|cause| write!(f, ": {}", cause))
+            }
+            Self::Unimplemented => write!(f, "unimplemented"),
+        }
+    }
+}
 
-pub trait Day {
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Self::Integer(n)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        i64::try_from(n).map_or_else(|e| Self::error(&e), Self::Integer)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+impl From<Vec<String>> for Answer {
+    fn from(rows: Vec<String>) -> Self {
+        Self::Grid(rows)
+    }
+}
+
+impl<T: Into<Answer>> From<BoxResult<T>> for Answer {
+    fn from(r: BoxResult<T>) -> Self {
+        r.map_or_else(|e| Self::error(e.as_ref()), |v| v.into())
+    }
+}
+
+/// Named puzzle parameters, e.g. the row Day 15 looks at, which differs
+/// between the example and the real input.  Values are kept as text and
+/// parsed by the day asking for them.  A name qualified by a part, e.g.
+/// "part2.minutes", only applies to that part.  Each day declares the
+/// parameters it takes, with their defaults, which overrides are laid over.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Params {
+    values: BTreeMap<String, String>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(name.to_owned(), value.to_owned());
+    }
+
+    pub fn with(mut self, name: &str, value: &str) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// The part a parameter name is for, and the name within it, if it is
+    /// for only one part, e.g. `(2, "minutes")` for "part2.minutes".
+    fn split_part(name: &str) -> Option<(usize, &str)> {
+        [1, 2].into_iter().find_map(|part| {
+            name.strip_prefix(&format!("part{}.", part))
+                .map(|name| (part, name))
+        })
+    }
+
+    /// The parameters as seen by the given part.
+    pub fn for_part(&self, part: usize) -> Self {
+        let mut rv = Self::default();
+        for (name, value) in &self.values {
+            if Self::split_part(name).is_none() {
+                rv.set(name, value);
+            }
+        }
+        for (name, value) in &self.values {
+            match Self::split_part(name) {
+                Some((p, name)) if p == part => rv.set(name, value),
+                _ => {}
+            }
+        }
+        rv
+    }
+
+    /// These parameters with `overrides` laid over them.  An override of a
+    /// name for both parts replaces the values for either part, too.
+    pub fn overlay(&self, overrides: &Params) -> Self {
+        let mut rv = self.to_owned();
+        for (name, value) in overrides.iter() {
+            if Self::split_part(name).is_none() {
+                rv.values
+                    .retain(|n, _| Self::split_part(n).is_none_or(|(_, n)| n != name));
+            }
+            rv.set(name, value);
+        }
+        rv
+    }
+
+    /// Whether a parameter of the name, for both parts or only one, is
+    /// among these, e.g. "minutes" or "part1.minutes" for a day declaring
+    /// either.
+    pub fn declares(&self, name: &str) -> bool {
+        let base = |name: &str| {
+            Self::split_part(name)
+                .map_or(name, |(_, base)| base)
+                .to_owned()
+        };
+        let name = base(name);
+        self.values.keys().any(|n| base(n) == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn get<T>(&self, name: &str) -> BoxResult<T>
+    where
+        T: std::str::FromStr,
+        T::Err: Into<Box<dyn error::Error>>,
+    {
+        let value = self
+            .values
+            .get(name)
+            .with_reason(Reason::MissingField)
+            .with_token(name)?;
+        value
+            .parse()
+            .with_reason(Reason::Parse)
+            .with_token(&format!("{}={}", name, value))
+    }
+}
+
+/// Parameters are written "NAME=VALUE", separated by spaces.
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.iter().map(|(n, v)| format!("{}={}", n, v)).join(" ")
+        )
+    }
+}
+
+/// A puzzle solver.  Days are shared between the runner's worker threads.
+/// The parts are given the day's `params`, with any overrides, as seen by
+/// the part.
+pub trait Day: Send + Sync {
     fn tag(&self) -> &str;
-    fn part1(&self, _input: &dyn Fn() -> Box<dyn io::Read>) {}
-    fn part2(&self, _input: &dyn Fn() -> Box<dyn io::Read>) {}
+    /// The parameters the day takes, with their defaults.
+    fn params(&self) -> Params {
+        Params::new()
+    }
+    fn part1(&self, _input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        Answer::Unimplemented
+    }
+    fn part2(&self, _input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        Answer::Unimplemented
+    }
+    /// A random input in the format the parts read, for the given parameters,
+    /// as seen by either part.  The size is up to the day, e.g. a number of
+    /// lines or the side of a grid, and kept within what its parts can take.
+    fn generate(&self, _rng: &mut Rng, _size: usize, _params: &Params) -> BoxResult<String> {
+        Err(SimpleError::new(format!(
+            "day {} has no input generator",
+            self.tag()
+        )))?
+    }
+    /// Whether the day has a `reference` solution of the part.
+    fn has_reference(&self, _part: usize) -> bool {
+        false
+    }
+    /// Parameters laid over those of `params` when checking the parts
+    /// against their references, small enough for the references to solve
+    /// many inputs quickly.
+    fn reference_params(&self) -> Params {
+        Params::new()
+    }
+    /// A slow but plainly correct solution of a part, free of the shortcuts
+    /// the part takes, to check it against on small inputs.
+    fn reference(
+        &self,
+        _part: usize,
+        _input: &dyn Fn() -> Box<dyn io::Read>,
+        _params: &Params,
+    ) -> Answer {
+        Answer::Unimplemented
+    }
 }
 
 pub struct Utils;
 
-#[allow(unused)]
 impl Utils {
     pub fn numbers(input: &mut dyn io::Read) -> impl Iterator<Item = BoxResult<i64>> + '_ {
         let lines = io::BufReader::new(input).lines();
-        lines.map(|rs| {
-            rs.map_err(|e| e.into())
-                .and_then(|s| s.parse::<i64>().map_err(|e| e.into()))
+        lines.enumerate().map(|(i, rs)| {
+            let s = rs?;
+            s.parse::<i64>().at_line(i + 1).with_token(&s)
         })
     }
 
@@ -40,16 +443,85 @@ impl Utils {
         radix: u32,
     ) -> impl Iterator<Item = BoxResult<i64>> + '_ {
         let lines = io::BufReader::new(input).lines();
-        lines.map(move |rs| {
-            rs.map_err(|e| e.into())
-                .and_then(move |s| i64::from_str_radix(&s, radix).map_err(|e| e.into()))
+        lines.enumerate().map(move |(i, rs)| {
+            let s = rs?;
+            i64::from_str_radix(&s, radix).at_line(i + 1).with_token(&s)
         })
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context() {
+        let r: BoxResult<i64> = "1x".parse::<i64>().with_token("1x").at_line(3);
+        let e = r.in_day("01").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "parse failure in day 01 at line 3 near \"1x\""
+        );
+        assert_eq!(
+            Answer::error(e.as_ref()),
+            Answer::Error {
+                message: e.to_string(),
+                causes: vec!["invalid digit found in string".to_owned()],
+            }
+        );
+        let r: BoxResult<()> = None.with_reason(Reason::NoSolution);
+        assert_eq!(r.unwrap_err().to_string(), "no solution found");
+    }
+
+    #[test]
+    fn numbers() {
+        let mut input = "12\n-3\nx4\n".as_bytes();
+        let numbers = Utils::numbers(&mut input).collect::<Vec<_>>();
+        assert_eq!(numbers[0].as_ref().unwrap(), &12);
+        assert_eq!(numbers[1].as_ref().unwrap(), &-3);
+        assert_eq!(
+            numbers[2].as_ref().unwrap_err().to_string(),
+            "parse failure at line 3 near \"x4\""
+        );
+        let mut input = "ff\ng\n".as_bytes();
+        let numbers = Utils::numbers2(&mut input, 16).collect::<Vec<_>>();
+        assert_eq!(numbers[0].as_ref().unwrap(), &255);
+        assert_eq!(
+            numbers[1].as_ref().unwrap_err().to_string(),
+            "parse failure at line 2 near \"g\""
+        );
+    }
 
-    pub fn byte_matrix(input: &mut dyn io::Read) -> BoxResult<Vec<Vec<u8>>> {
-        io::BufReader::new(input)
-            .split(b'\n')
-            .map(|rv| rv.map_err(|e| e.into()))
-            .collect()
+    #[test]
+    fn params() {
+        let defaults = Params::new()
+            .with("part1.minutes", "30")
+            .with("part2.minutes", "26")
+            .with("layout", "input");
+        assert!(defaults.declares("minutes") && defaults.declares("part2.layout"));
+        assert!(!defaults.declares("rounds"));
+        let params = defaults.overlay(&Params::new().with("part2.layout", "example"));
+        assert_eq!(params.for_part(1).get::<usize>("minutes").unwrap(), 30);
+        assert_eq!(params.for_part(1).get::<String>("layout").unwrap(), "input");
+        assert_eq!(
+            params.for_part(2).get::<String>("layout").unwrap(),
+            "example"
+        );
+        let params = defaults.overlay(&Params::new().with("minutes", "5"));
+        assert_eq!(params.for_part(2).get::<usize>("minutes").unwrap(), 5);
+        assert_eq!(params.to_string(), "layout=input minutes=5");
+        let e = params.get::<usize>("layout").unwrap_err();
+        assert_eq!(e.to_string(), "parse failure near \"layout=input\"");
+        let e = params.get::<usize>("rounds").unwrap_err();
+        assert_eq!(e.to_string(), "missing field near \"rounds\"");
+        let params = Params::new()
+            .with("particles", "3")
+            .with("part2.particles", "4")
+            .with("part3.particles", "5");
+        assert_eq!(params.for_part(1).get::<usize>("particles").unwrap(), 3);
+        assert_eq!(params.for_part(2).get::<usize>("particles").unwrap(), 4);
+        assert!(params.declares("particles") && !params.declares("ticles"));
+        let params = params.overlay(&Params::new().with("particles", "6"));
+        assert_eq!(params.to_string(), "part3.particles=5 particles=6");
     }
 }
@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::iter;
 
 pub struct Day01 {}
@@ -10,42 +12,52 @@ impl Day for Day01 {
         "01"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
 impl Day01 {
     fn process(input: &mut dyn io::Read, window: usize) -> BoxResult<Output> {
-        Ok(io::BufReader::new(input)
-            .lines()
-            .group_by(|r| r.as_ref().map_or(false, |s| s.is_empty()))
-            .into_iter()
-            .filter(|&(is_blank, _)| !is_blank)
-            .map(|(_, elf)| {
-                elf.map(|calories| {
-                    calories
-                        .map_err(|e| e.into())
-                        .and_then(|s| s.parse::<Output>().map_err(|e| e.into()))
+        let elves = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .enumerate()
+                .group_by(|(_, r)| r.as_ref().is_ok_and(|s| s.is_empty()))
+                .into_iter()
+                .filter(|&(is_blank, _)| !is_blank)
+                .map(|(_, elf)| {
+                    elf.map(|(i, calories)| {
+                        let calories = calories?;
+                        calories
+                            .parse::<Output>()
+                            .at_line(i + 1)
+                            .with_token(&calories)
+                    })
+                    .collect::<BoxResult<Vec<_>>>()
                 })
-                .sum()
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        Ok(elves
+            .into_iter()
+            .map(|elf| elf.into_iter().sum::<Output>())
+            .fold(Vec::new(), |max_calories, calories| {
+                max_calories
+                    .into_iter()
+                    .chain(iter::once(calories))
+                    .sorted()
+                    .rev()
+                    .take(window)
+                    .collect()
             })
-            .try_fold(
-                Vec::new(),
-                |max_calories, calories: BoxResult<_>| -> BoxResult<Vec<Output>> {
-                    Ok(max_calories
-                        .into_iter()
-                        .chain(iter::once(calories?))
-                        .sorted()
-                        .rev()
-                        .take(window)
-                        .collect())
-                },
-            )?
             .into_iter()
             .sum())
     }
@@ -57,59 +69,16 @@ impl Day01 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::process(input, 3)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day01 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "1000
-2000
-3000
-
-4000
-
-5000
-6000
 
-7000
-8000
-9000
-
-10000",
-            24000,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day01 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "1000
-2000
-3000
-
-4000
-
-5000
-6000
-
-7000
-8000
-9000
-
-10000",
-            45000,
-        );
+    /// The snacks of `size` elves.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                (0..rng.range(1..=12))
+                    .map(|_| rng.range(1000..=60000).to_string())
+                    .join("\n")
+            })
+            .join("\n\n")
+            + "\n"
     }
 }
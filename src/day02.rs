@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::str::FromStr;
 
 pub struct Day02 {}
@@ -10,12 +12,16 @@ impl Day for Day02 {
         "02"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -62,7 +68,7 @@ impl FromStr for Choice {
             "A" | "X" => Ok(Self::Rock),
             "B" | "Y" => Ok(Self::Paper),
             "C" | "Z" => Ok(Self::Scissors),
-            _ => Err(AocError),
+            _ => Err(AocError::new(Reason::Parse).token(s)),
         }
     }
 }
@@ -100,7 +106,7 @@ impl FromStr for Outcome {
             "X" => Ok(Self::Loss),
             "Y" => Ok(Self::Draw),
             "Z" => Ok(Self::Win),
-            _ => Err(AocError),
+            _ => Err(AocError::new(Reason::Parse).token(s)),
         }
     }
 }
@@ -110,17 +116,30 @@ impl Day02 {
     where
         F: Fn(&str, &Choice) -> BoxResult<(Choice, Outcome)>,
     {
-        io::BufReader::new(input)
-            .lines()
-            .map(|r| {
-                let s = r?;
-                let mut tokens = s.split_whitespace();
-                let opponent = tokens.next().ok_or(AocError)?.parse()?;
-                let token = tokens.next().ok_or(AocError)?;
-                let (me, outcome) = f(token, &opponent)?;
-                Ok(me.value() + outcome.value())
-            })
-            .sum()
+        let rounds = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .enumerate()
+                .map(|(i, r)| {
+                    let s = r?;
+                    let mut tokens = s.split_whitespace();
+                    let opponent = tokens
+                        .next()
+                        .with_reason(Reason::MissingField)
+                        .and_then(|token| token.parse::<Choice>().map_err(|e| e.into()))
+                        .at_line(i + 1)?;
+                    let token = tokens
+                        .next()
+                        .with_reason(Reason::MissingField)
+                        .at_line(i + 1)?;
+                    f(token, &opponent).at_line(i + 1)
+                })
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        Ok(rounds
+            .iter()
+            .map(|(me, outcome)| me.value() + outcome.value())
+            .sum())
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
@@ -136,39 +155,17 @@ impl Day02 {
             Ok((outcome.opponent(opponent), outcome))
         })
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day02 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "A Y
-B X
-C Z
-",
-            15,
-        );
-    }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day02 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "A Y
-B X
-C Z
-",
-            12,
-        );
+    /// A strategy guide of `size` rounds.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{} {}\n",
+                    rng.pick(&["A", "B", "C"]),
+                    rng.pick(&["X", "Y", "Z"])
+                )
+            })
+            .collect()
     }
 }
@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use byte_set::ByteSet;
 
 pub struct Day03 {}
@@ -10,44 +12,58 @@ impl Day for Day03 {
         "03"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
 impl Day03 {
+    fn rucksacks(input: &mut dyn io::Read) -> BoxResult<Vec<String>> {
+        timing::parsing(|| {
+            Ok(io::BufReader::new(input)
+                .lines()
+                .collect::<Result<_, _>>()?)
+        })
+    }
+
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
+        Self::rucksacks(input)?
+            .iter()
             .map(|rucksack| {
-                let rucksack = rucksack?;
                 let (comp1, comp2) = rucksack.split_at(rucksack.len() / 2); // XXX panics
                 let (comp1, comp2): (ByteSet, ByteSet) = (comp1.into(), comp2.into());
-                let duplicate = comp1.intersection(comp2).first().ok_or(AocError)?;
+                let duplicate = comp1
+                    .intersection(comp2)
+                    .first()
+                    .with_reason(Reason::NoSolution)?;
                 Ok(Self::priority(duplicate)?)
             })
             .sum()
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
+        Self::rucksacks(input)?
+            .iter()
             .tuples()
             .map(|(sack1, sack2, sack3)| {
                 let (sack1, sack2, sack3): (ByteSet, ByteSet, ByteSet) = (
-                    sack1?.as_str().into(),
-                    sack2?.as_str().into(),
-                    sack3?.as_str().into(),
+                    sack1.as_str().into(),
+                    sack2.as_str().into(),
+                    sack3.as_str().into(),
                 );
                 let badge = (sack1
                     .intersection(sack2)
                     .intersection(sack3)
                     .first()
-                    .ok_or_else(|| AocError.into()) as BoxResult<_>)?;
+                    .with_reason(Reason::NoSolution))?;
                 Ok(Self::priority(badge)?)
             })
             .sum()
@@ -57,46 +73,44 @@ impl Day03 {
         match item {
             b'a'..=b'z' => Ok((1 + (item - b'a')) as Output),
             b'A'..=b'Z' => Ok((27 + (item - b'A')) as Output),
-            _ => Err(AocError),
+            _ => Err(AocError::new(Reason::Parse).token(&(item as char))),
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day03 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw",
-            157,
-        );
-    }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day03 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw",
-            70,
-        );
+    /// The rucksacks of `size` groups of three elves.  The items of each elf
+    /// of a group are drawn from letters of its own, so that the group has
+    /// exactly one badge, and a rucksack exactly one item in both of its
+    /// compartments.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let letters = (b'a'..=b'z').chain(b'A'..=b'Z').collect::<Vec<_>>();
+        let mut s = String::new();
+        for _ in 0..size {
+            let mut letters = letters.to_owned();
+            rng.shuffle(&mut letters);
+            let badge = letters[0];
+            for own in letters[1..].chunks(letters.len() / 3).take(3) {
+                let (first, second) = own.split_at(own.len() / 2);
+                let duplicate = *rng.pick(own);
+                let len = rng.range(4..=16);
+                let mut compartments = [(vec![badge, duplicate], first), (vec![duplicate], second)]
+                    .map(|(mut compartment, others)| {
+                        let others = others
+                            .iter()
+                            .filter(|&&b| b != duplicate)
+                            .collect::<Vec<_>>();
+                        while compartment.len() < len {
+                            compartment.push(**rng.pick(&others));
+                        }
+                        rng.shuffle(&mut compartment);
+                        compartment
+                    });
+                if rng.chance(0.5) {
+                    compartments.swap(0, 1);
+                }
+                s.extend(compartments.iter().flatten().map(|&b| b as char));
+                s.push('\n');
+            }
+        }
+        s
     }
 }
@@ -1,6 +1,7 @@
 use crate::day::*;
-use std::ops::Range;
-use std::str::FromStr;
+use crate::interval::IntervalSet;
+use crate::random::Rng;
+use crate::timing;
 
 pub struct Day04 {}
 
@@ -11,57 +12,53 @@ impl Day for Day04 {
         "04"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
-}
-
-struct Section(Range<usize>);
 
-impl Section {
-    fn contains(a: &Section, b: &Section) -> bool {
-        let (a, b) = (&a.0, &b.0);
-        a.start <= b.start && a.end >= b.end || b.start <= a.start && b.end >= a.end
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
+}
 
-    fn overlaps(a: &Section, b: &Section) -> bool {
-        let (a, b) = (&a.0, &b.0);
-        !(a.end < b.start || a.start > b.end)
-    }
+// The sections an elf is assigned to.
+type Section = IntervalSet;
+
+fn contains(a: &Section, b: &Section) -> bool {
+    a.is_superset(b) || b.is_superset(a)
 }
 
-impl FromStr for Section {
-    type Err = AocError;
+fn overlaps(a: &Section, b: &Section) -> bool {
+    !a.is_disjoint(b)
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut i = s.split('-');
-        let section = Section(
-            i.next().ok_or(AocError)?.parse().or(Err(AocError))?
-                ..i.next().ok_or(AocError)?.parse().or(Err(AocError))?,
-        );
-        if i.next().is_none() {
-            Ok(section)
-        } else {
-            Err(AocError)
-        }
-    }
+fn section(s: &str) -> Result<Section, AocError> {
+    let invalid = || AocError::new(Reason::Parse).token(s);
+    let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+    Ok(Section::from(
+        start.parse().or(Err(invalid()))?..=end.parse().or(Err(invalid()))?,
+    ))
 }
 
 impl Day04 {
     fn parse(s: &str) -> BoxResult<(Section, Section)> {
         let mut i = s.split(',');
         let pair = (
-            i.next().ok_or(AocError).and_then(Section::from_str)?,
-            i.next().ok_or(AocError).and_then(Section::from_str)?,
+            i.next()
+                .with_reason(Reason::MissingField)
+                .and_then(|s| Ok(section(s)?))?,
+            i.next()
+                .with_reason(Reason::MissingField)
+                .and_then(|s| Ok(section(s)?))?,
         );
         if i.next().is_none() {
             Ok(pair)
         } else {
-            Err(AocError)?
+            Err(AocError::new(Reason::Parse).token(s))?
         }
     }
 
@@ -69,57 +66,34 @@ impl Day04 {
     where
         F: Fn(&Section, &Section) -> bool,
     {
-        Ok(io::BufReader::new(input)
-            .lines()
-            .map(|l| Self::parse(&l?))
-            .filter(|pair| pair.as_ref().map_or(false, |pair| f(&pair.0, &pair.1)))
-            .count())
+        let pairs = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .enumerate()
+                .map(|(i, l)| Self::parse(&l?).at_line(i + 1))
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        Ok(pairs.iter().filter(|pair| f(&pair.0, &pair.1)).count())
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        Self::process(input, Section::contains)
+        Self::process(input, contains)
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        Self::process(input, Section::overlaps)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day04 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "2-4,6-8
-2-3,4-5
-5-7,7-9
-2-8,3-7
-6-6,4-6
-2-6,4-8",
-            2,
-        );
+        Self::process(input, overlaps)
     }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day04 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "2-4,6-8
-2-3,4-5
-5-7,7-9
-2-8,3-7
-6-6,4-6
-2-6,4-8",
-            4,
-        );
+    /// The section assignments of `size` pairs of elves.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let [a, b] = [(); 2].map(|_| {
+                    let start = rng.range(1..=99);
+                    (start, rng.range(start..=99))
+                });
+                format!("{}-{},{}-{}\n", a.0, a.1, b.0, b.1)
+            })
+            .collect()
     }
 }
@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::collections::HashMap;
 
 pub struct Day05 {}
@@ -10,23 +12,30 @@ impl Day for Day05 {
         "05"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
 const STACK_WIDTH: usize = 4;
 const STACK_OFFSET: usize = 1;
 
+// The crates of each stack, from the top, by the number of the stack.
+type Stacks = HashMap<usize, String>;
+
+// A move of a number of crates from one stack to another.
+type Move = (usize, usize, usize);
+
 impl Day05 {
-    fn process<F>(input: &mut dyn io::Read, select_crate: F) -> BoxResult<Output>
-    where
-        F: Fn(usize) -> usize,
-    {
+    fn parse(input: &mut dyn io::Read) -> BoxResult<(Stacks, Vec<Move>)> {
         let lines = &mut io::BufReader::new(input).lines();
         let stacks = lines
             .take_while(|r| r.as_ref().map_or(true, |l| l.contains('[')))
@@ -52,34 +61,37 @@ impl Day05 {
                         }))
                 },
             )?;
-        let stacks = lines.skip(1).fold(Ok(stacks), |stacks: BoxResult<_>, l| {
-            l?.split_whitespace()
-                .tuples()
-                .fold(stacks, |stacks, (_, count, _, from, _, to)| {
-                    let mut stacks = stacks?;
-                    let (count, from, to) = (
-                        count.parse::<usize>()?,
-                        from.parse::<usize>()?,
-                        to.parse::<usize>()?,
-                    );
-                    for i in (0..count).rev() {
-                        let source = stacks.get_mut(&from).ok_or(AocError)?;
-                        let top = source.remove(select_crate(i)); // XXX panics
-                        let target = stacks.get_mut(&to).ok_or(AocError)?;
-                        target.insert(0, top);
-                    }
-                    Ok(stacks)
-                })
-        })?;
-        let len = stacks.keys().max().ok_or(AocError)?;
+        let mut moves = vec![];
+        for l in lines.skip(1) {
+            for (_, count, _, from, _, to) in l?.split_whitespace().tuples() {
+                moves.push((count.parse()?, from.parse()?, to.parse()?));
+            }
+        }
+        Ok((stacks, moves))
+    }
+
+    fn process<F>(input: &mut dyn io::Read, select_crate: F) -> BoxResult<Output>
+    where
+        F: Fn(usize) -> usize,
+    {
+        let (mut stacks, moves) = timing::parsing(|| Self::parse(input))?;
+        for (count, from, to) in moves {
+            for i in (0..count).rev() {
+                let source = stacks.get_mut(&from).with_reason(Reason::OutOfBounds)?;
+                let top = source.remove(select_crate(i)); // XXX panics
+                let target = stacks.get_mut(&to).with_reason(Reason::OutOfBounds)?;
+                target.insert(0, top);
+            }
+        }
+        let len = stacks.keys().max().with_reason(Reason::Parse)?;
         (1..=*len)
             .map(|i| {
-                Ok(stacks
+                stacks
                     .get(&i)
                     .unwrap_or(&String::from(" "))
                     .chars()
                     .next()
-                    .ok_or(AocError)?)
+                    .with_reason(Reason::NoSolution)
             })
             .collect()
     }
@@ -91,49 +103,51 @@ impl Day05 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::process(input, |i| i)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day05 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "    [D]    
-[N] [C]    
-[Z] [M] [P]
- 1   2   3 
-
-move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2",
-            String::from("CMZ"),
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day05 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "    [D]    
-[N] [C]    
-[Z] [M] [P]
- 1   2   3 
-
-move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2",
-            String::from("MCD"),
-        );
+    /// A drawing of up to nine stacks of crates and `size` moves between them,
+    /// none of which takes a stack's last crate, so that every stack has a
+    /// top crate in the end.  With at least two crates per stack to begin
+    /// with, some stack always has a crate to spare.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let mut stacks = (0..rng.range(3..=9))
+            .map(|_| {
+                (0..rng.range(2..=8))
+                    .map(|_| rng.range(b'A'..=b'Z') as char)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+        let mut s = (0..height)
+            .rev()
+            .map(|level| {
+                let row = stacks
+                    .iter()
+                    .map(|stack| match stack.get(level) {
+                        Some(c) => format!("[{}]", c),
+                        None => "   ".to_owned(),
+                    })
+                    .join(" ");
+                row.trim_end().to_owned() + "\n"
+            })
+            .collect::<String>();
+        s += &(1..=stacks.len()).map(|i| format!(" {} ", i)).join(" ");
+        s += "\n\n";
+        let mut moves = 0;
+        while moves < size {
+            let (from, to) = (
+                rng.range(0..=stacks.len() - 1),
+                rng.range(0..=stacks.len() - 1),
+            );
+            if from == to || stacks[from].len() < 2 {
+                continue;
+            }
+            let count = rng.range(1..=stacks[from].len() - 1);
+            let at = stacks[from].len() - count;
+            let moved = stacks[from].split_off(at);
+            stacks[to].extend(moved);
+            s += &format!("move {} from {} to {}\n", count, from + 1, to + 1);
+            moves += 1;
+        }
+        s
     }
 }
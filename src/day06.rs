@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use std::collections::VecDeque;
 use std::io::Read;
 
 pub struct Day06 {}
@@ -10,27 +12,46 @@ impl Day for Day06 {
         "06"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
 impl Day06 {
+    // Only the last `size` characters are kept, along with how many times
+    // each occurs in them and how many occur more than once, so that the
+    // datastream can be of any length and each character is seen once.
     fn scan(input: &mut dyn io::Read, size: usize) -> BoxResult<Output> {
-        let input = io::BufReader::new(input)
-            .bytes()
-            .collect::<Result<Vec<_>, _>>()?;
-        let (i, _) = input
-            .as_slice()
-            .windows(size)
-            .enumerate()
-            .find(|(_, w)| w.iter().duplicates().next().is_none())
-            .ok_or(AocError)?;
-        Ok(i + size)
+        let mut window = VecDeque::with_capacity(size + 1);
+        let mut counts = [0u32; 256];
+        let mut repeated = 0;
+        for (i, b) in io::BufReader::new(input).bytes().enumerate() {
+            let b = b?;
+            window.push_back(b);
+            counts[b as usize] += 1;
+            if counts[b as usize] == 2 {
+                repeated += 1;
+            }
+            if window.len() > size {
+                let b = window.pop_front().unwrap_or_default();
+                if counts[b as usize] == 2 {
+                    repeated -= 1;
+                }
+                counts[b as usize] -= 1;
+            }
+            if window.len() == size && repeated == 0 {
+                return Ok(i + 1);
+            }
+        }
+        Err(AocError::new(Reason::NoSolution).into())
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
@@ -40,35 +61,26 @@ impl Day06 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::scan(input, 14)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day06 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7);
-        test1("bvwbjplbgvbhsrlpgdmjqwftvncz", 5);
-        test1("nppdvjthqldpwncqszvftbrmjlhg", 6);
-        test1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10);
-        test1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11);
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day06 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
 
-    #[test]
-    fn part2() {
-        test2("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 19);
-        test2("bvwbjplbgvbhsrlpgdmjqwftvncz", 23);
-        test2("nppdvjthqldpwncqszvftbrmjlhg", 23);
-        test2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 29);
-        test2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 26);
+    /// A datastream of about `size` characters, at least the 14 of its
+    /// start-of-message marker, which comes after a stretch of a few letters
+    /// with no start-of-packet marker, then one of more letters without a
+    /// start-of-message marker.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let letters = (b'a'..=b'z').collect::<Vec<_>>();
+        let mut marker = letters.to_owned();
+        rng.shuffle(&mut marker);
+        let len = size.saturating_sub(14);
+        let stretch = |rng: &mut Rng, alphabet: &[u8], len| {
+            (0..len)
+                .map(|_| *rng.pick(alphabet) as char)
+                .collect::<String>()
+        };
+        let (before, after) = (len / 2, len - len / 2);
+        stretch(rng, &letters[..3], before / 2)
+            + &stretch(rng, &letters[..10], before - before / 2)
+            + &marker[..14].iter().map(|&b| b as char).collect::<String>()
+            + &stretch(rng, &letters, after)
+            + "\n"
     }
 }
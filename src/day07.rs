@@ -1,5 +1,8 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 
 pub struct Day07 {}
@@ -11,12 +14,16 @@ impl Day for Day07 {
         "07"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -45,7 +52,11 @@ impl Inode {
                     + map
                         .iter()
                         .filter(|(name, _)| *name != "/" && *name != "." && *name != "..")
-                        .map(|(_, ino)| fs.get(*ino).ok_or(AocError)?.size(fs))
+                        .map(|(_, ino)| {
+                            fs.get(*ino)
+                                .ok_or_else(|| AocError::new(Reason::OutOfBounds))?
+                                .size(fs)
+                        })
                         .sum::<Result<usize, _>>()?
             }
         })
@@ -63,7 +74,9 @@ impl Inode {
                 map.iter()
                     .filter(|(name, _)| *name != "/" && *name != "." && *name != "..")
                     .fold(state, |state, (_name, ino)| {
-                        let inode = fs.get(*ino).ok_or(AocError)?;
+                        let inode = fs
+                            .get(*ino)
+                            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
                         inode.dfs_dir_fold(fs, state?, f)
                     })
             }
@@ -93,17 +106,25 @@ impl FileSystem {
     }
 
     fn get_ino(&self, cwd: usize, name: &str) -> Result<usize, AocError> {
-        let inode = self.inner.get(cwd).ok_or(AocError)?;
+        let inode = self
+            .inner
+            .get(cwd)
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
         if let Type::Directory(map) = &inode.ty {
-            map.get(name).ok_or(AocError).map(|i| *i)
+            map.get(name)
+                .ok_or_else(|| AocError::new(Reason::Parse).token(name))
+                .copied()
         } else {
-            Err(AocError)?
+            Err(AocError::new(Reason::Parse).token(name))
         }
     }
 
     fn mkdir(&mut self, cwd: usize, name: &str) -> Result<(), AocError> {
         let next_ino = self.inner.len();
-        let inode = self.inner.get_mut(cwd).ok_or(AocError)?;
+        let inode = self
+            .inner
+            .get_mut(cwd)
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
         let dir = if let Type::Directory(map) = &mut inode.ty {
             let mut children = HashMap::new();
             children.insert(".".to_owned(), next_ino);
@@ -112,7 +133,7 @@ impl FileSystem {
             map.insert(name.to_owned(), next_ino);
             Ok(dir)
         } else {
-            Err(AocError)
+            Err(AocError::new(Reason::Parse).token(name))
         }?;
         self.inner.push(dir);
         Ok(())
@@ -120,13 +141,16 @@ impl FileSystem {
 
     fn mkfile(&mut self, cwd: usize, name: &str, size: usize) -> Result<(), AocError> {
         let next_ino = self.inner.len();
-        let inode = self.inner.get_mut(cwd).ok_or(AocError)?;
+        let inode = self
+            .inner
+            .get_mut(cwd)
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
         let file = if let Type::Directory(map) = &mut inode.ty {
             let file = Inode::new(Type::File, size);
             map.insert(name.to_owned(), next_ino);
             Ok(file)
         } else {
-            Err(AocError)
+            Err(AocError::new(Reason::Parse).token(name))
         }?;
         self.inner.push(file);
         Ok(())
@@ -138,8 +162,8 @@ impl FileSystem {
         S: Debug,
     {
         self.inner
-            .get(0)
-            .ok_or(AocError)
+            .first()
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))
             .and_then(|root| root.dfs_dir_fold(self, init, f))
     }
 }
@@ -158,25 +182,26 @@ impl Day07 {
                         match tokens.next() {
                             Some("$") => match tokens.next() {
                                 Some("cd") => {
-                                    let name = tokens.next().ok_or(AocError)?;
+                                    let name = tokens.next().with_reason(Reason::MissingField)?;
                                     cwd = Some(if name == "/" {
                                         0
                                     } else {
-                                        fs.get_ino(cwd.ok_or(AocError)?, name)?
+                                        fs.get_ino(cwd.with_reason(Reason::Parse)?, name)?
                                     });
                                 }
                                 Some("ls") => (),
-                                _ => Err(AocError)?,
+                                Some(command) => Err(AocError::new(Reason::Parse).token(command))?,
+                                None => Err(AocError::new(Reason::MissingField))?,
                             },
                             Some("dir") => {
-                                let name = tokens.next().ok_or(AocError)?;
-                                fs.mkdir(cwd.ok_or(AocError)?, name)?;
+                                let name = tokens.next().with_reason(Reason::MissingField)?;
+                                fs.mkdir(cwd.with_reason(Reason::Parse)?, name)?;
                             }
                             Some(size) => {
-                                let name = tokens.next().ok_or(AocError)?;
-                                fs.mkfile(cwd.ok_or(AocError)?, name, size.parse()?)?;
+                                let name = tokens.next().with_reason(Reason::MissingField)?;
+                                fs.mkfile(cwd.with_reason(Reason::Parse)?, name, size.parse()?)?;
                             }
-                            _ => Err(AocError)?,
+                            None => Err(AocError::new(Reason::MissingField))?,
                         }
                         Ok((fs, cwd))
                     } else {
@@ -189,8 +214,8 @@ impl Day07 {
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let fs = Self::parse(input)?;
-        // println!("{:#?}", fs);
+        let fs = timing::parsing(|| Self::parse(input))?;
+        trace!(3, "{:#?}", fs);
         let size = fs.dfs(0, |inode: &Inode, total: usize| {
             inode
                 .size(&fs)
@@ -200,11 +225,11 @@ impl Day07 {
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let fs = Self::parse(input)?;
+        let fs = timing::parsing(|| Self::parse(input))?;
         let limit = 30000000
             - (70000000
                 - fs.get(0)
-                    .ok_or(AocError)
+                    .ok_or_else(|| AocError::new(Reason::OutOfBounds))
                     .and_then(|inode| inode.size(&fs))?); // XXX loses error info
         let size = fs.dfs(None, |inode: &Inode, best: Option<usize>| {
             inode.size(&fs).map(|size| {
@@ -223,79 +248,94 @@ impl Day07 {
                 }
             })
         })?;
-        size.ok_or_else(|| AocError.into())
+        size.with_reason(Reason::NoSolution)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day07 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "$ cd /
-$ ls
-dir a
-14848514 b.txt
-8504156 c.dat
-dir d
-$ cd a
-$ ls
-dir e
-29116 f
-2557 g
-62596 h.lst
-$ cd e
-$ ls
-584 i
-$ cd ..
-$ cd ..
-$ cd d
-$ ls
-4060174 j
-8033020 d.log
-5626152 d.ext
-7214296 k",
-            95437,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day07 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "$ cd /
-$ ls
-dir a
-14848514 b.txt
-8504156 c.dat
-dir d
-$ cd a
-$ ls
-dir e
-29116 f
-2557 g
-62596 h.lst
-$ cd e
-$ ls
-584 i
-$ cd ..
-$ cd ..
-$ cd d
-$ ls
-4060174 j
-8033020 d.log
-5626152 d.ext
-7214296 k",
-            24933642,
+    /// The terminal output of exploring a random tree of `size` directories,
+    /// depth first, with files mostly small, as in the inputs, and large
+    /// ones added or all scaled down so that between 42000000 and 68000000
+    /// of the disk is used, as part 2 needs.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let count = size.max(1);
+        let mut children = vec![vec![]; count];
+        for dir in 1..count {
+            children[rng.range(0..=dir - 1)].push(dir);
+        }
+        let mut files = (0..count)
+            .map(|dir| {
+                let least = if dir == 0 { 1 } else { 0 };
+                (0..rng.range(least..=4))
+                    .map(|_| {
+                        let digits = rng.range(3..=5);
+                        rng.range(1..=3 * 10usize.pow(digits))
+                    })
+                    .collect::<Vec<usize>>()
+            })
+            .collect::<Vec<_>>();
+        let (used, total) = (
+            rng.range(42000000..=68000000),
+            files.iter().flatten().sum::<usize>(),
         );
+        if total > used {
+            for size in files.iter_mut().flatten() {
+                *size = (*size * used / total).max(1);
+            }
+        } else {
+            let mut missing = used - total;
+            while missing > 0 {
+                let size = missing.min(rng.range(1000000..=20000000));
+                files[rng.range(0..=count - 1)].push(size);
+                missing -= size;
+            }
+        }
+        fn name(rng: &mut Rng, taken: &mut HashSet<String>, extension: bool) -> String {
+            loop {
+                let mut name = (0..rng.range(1..=8))
+                    .map(|_| rng.range(b'a'..=b'z') as char)
+                    .collect::<String>();
+                if extension && rng.chance(0.5) {
+                    name += &format!(".{}", rng.pick(&["dat", "txt", "log", "bin"]));
+                }
+                if taken.insert(name.to_owned()) {
+                    return name;
+                }
+            }
+        }
+        fn explore(
+            rng: &mut Rng,
+            dir: usize,
+            children: &[Vec<usize>],
+            files: &[Vec<usize>],
+            s: &mut String,
+        ) {
+            let mut taken = HashSet::new();
+            let subdirs = children[dir]
+                .iter()
+                .map(|&child| (child, name(rng, &mut taken, false)))
+                .collect::<Vec<_>>();
+            let mut entries = subdirs
+                .iter()
+                .map(|(_, name)| format!("dir {}", name))
+                .chain(
+                    files[dir]
+                        .iter()
+                        .map(|size| format!("{} {}", size, name(rng, &mut taken, true))),
+                )
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut entries);
+            *s += "$ ls\n";
+            for entry in entries {
+                *s += &entry;
+                s.push('\n');
+            }
+            for (child, name) in subdirs {
+                *s += &format!("$ cd {}\n", name);
+                explore(rng, child, children, files, s);
+                *s += "$ cd ..\n";
+            }
+        }
+        let mut s = "$ cd /\n".to_owned();
+        explore(rng, 0, &children, &files, &mut s);
+        s
     }
 }
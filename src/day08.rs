@@ -1,4 +1,7 @@
 use crate::day::*;
+use crate::grid::*;
+use crate::random::Rng;
+use crate::timing;
 
 pub struct Day08 {}
 
@@ -9,220 +12,76 @@ impl Day for Day08 {
         "08"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
-}
-
-type Forest = Vec<Vec<u8>>;
 
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
+    }
 }
 
 impl Day08 {
-    fn tree(forest: &Forest, x: usize, y: usize) -> Result<u8, AocError> {
-        forest
-            .get(y)
-            .ok_or(AocError)?
-            .get(x)
-            .ok_or(AocError)
-            .map(|t| *t)
+    fn parse(input: &mut dyn io::Read) -> BoxResult<Grid<u8>> {
+        Grid::parse(input, |c| c.to_digit(10).map(|h| h as u8))
     }
 
-    // fn step(p: (usize, usize), d: (i8, i8)) -> Option<(usize, usize)> {}
-
-    fn is_visible_in_dir(
-        forest: &Forest,
-        xs: usize,
-        ys: usize,
-        x: usize,
-        y: usize,
-        dir: Dir,
-    ) -> BoxResult<bool> {
-        let h = Self::tree(forest, x, y)?;
-        match dir {
-            Dir::Up => {
-                for yy in 0..y {
-                    if Self::tree(forest, x, yy)? >= h {
-                        return Ok(false);
-                    }
-                }
-                Ok(true)
-            }
-            Dir::Down => {
-                for yy in y + 1..ys {
-                    if Self::tree(forest, x, yy)? >= h {
-                        return Ok(false);
-                    }
-                }
-                Ok(true)
-            }
-            Dir::Left => {
-                for xx in 0..x {
-                    if Self::tree(forest, xx, y)? >= h {
-                        return Ok(false);
-                    }
-                }
-                Ok(true)
-            }
-            Dir::Right => {
-                for xx in x + 1..xs {
-                    if Self::tree(forest, xx, y)? >= h {
-                        return Ok(false);
-                    }
-                }
-                Ok(true)
-            }
-        }
-    }
-
-    fn is_visible(forest: &Forest, xs: usize, ys: usize, x: usize, y: usize) -> BoxResult<bool> {
-        Ok(x == 0
-            || y == 0
-            || x == xs - 1
-            || y == ys - 1
-            || Self::is_visible_in_dir(forest, xs, ys, x, y, Dir::Up)?
-            || Self::is_visible_in_dir(forest, xs, ys, x, y, Dir::Down)?
-            || Self::is_visible_in_dir(forest, xs, ys, x, y, Dir::Left)?
-            || Self::is_visible_in_dir(forest, xs, ys, x, y, Dir::Right)?)
+    // A tree is visible if all trees in some direction are lower.
+    fn is_visible(forest: &Grid<u8>, pos: Pos) -> bool {
+        let h = forest[pos];
+        ORTHOGONAL
+            .into_iter()
+            .any(|dir| forest.ray(pos, dir).all(|tree| forest[tree] < h))
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let forest = Utils::byte_matrix(input)?;
-        let ys = forest.len();
-        let xs = forest.get(0).ok_or(AocError)?.len();
-        (0..ys).fold(Ok(0), |c, y| {
-            (0..xs).fold(c, |c, x| {
-                if Self::is_visible(&forest, xs, ys, x, y)? {
-                    c.map(|c| c + 1)
-                } else {
-                    c
-                }
-            })
-        })
+        let forest = timing::parsing(|| Self::parse(input))?;
+        Ok(forest
+            .positions()
+            .filter(|&pos| Self::is_visible(&forest, pos))
+            .count())
     }
 
-    fn visible_trees(
-        forest: &Forest,
-        xs: usize,
-        ys: usize,
-        x: usize,
-        y: usize,
-        dir: Dir,
-    ) -> BoxResult<usize> {
-        let h = Self::tree(forest, x, y)?;
-        match dir {
-            Dir::Up => (0..y)
-                .rev()
-                .map(|yy| Self::tree(forest, x, yy))
-                .fold(Ok((false, 0)), |state, hh| {
-                    let (blocked, count) = state?;
-                    Ok((
-                        blocked || hh? >= h,
-                        if !blocked { count + 1 } else { count },
-                    ))
-                })
-                .map(|(_, c)| c),
-            Dir::Down => ((y + 1)..ys)
-                .map(|yy| Self::tree(forest, x, yy))
-                .fold(Ok((false, 0)), |state, hh| {
-                    let (blocked, count) = state?;
-                    Ok((
-                        blocked || hh? >= h,
-                        if !blocked { count + 1 } else { count },
-                    ))
-                })
-                .map(|(_, c)| c),
-            Dir::Left => (0..x)
-                .rev()
-                .map(|xx| Self::tree(forest, xx, y))
-                .fold(Ok((false, 0)), |state, hh| {
-                    let (blocked, count) = state?;
-                    Ok((
-                        blocked || hh? >= h,
-                        if !blocked { count + 1 } else { count },
-                    ))
-                })
-                .map(|(_, c)| c),
-            Dir::Right => ((x + 1)..xs)
-                .map(|xx| Self::tree(forest, xx, y))
-                .fold(Ok((false, 0)), |state, hh| {
-                    let (blocked, count) = state?;
-                    Ok((
-                        blocked || hh? >= h,
-                        if !blocked { count + 1 } else { count },
-                    ))
-                })
-                .map(|(_, c)| c),
-        }
+    // The trees seen in a direction are the lower ones up to, and including,
+    // the first one at least as high.
+    fn visible_trees(forest: &Grid<u8>, pos: Pos, dir: (isize, isize)) -> usize {
+        let h = forest[pos];
+        let lower = forest
+            .ray(pos, dir)
+            .take_while(|&tree| forest[tree] < h)
+            .count();
+        (lower + 1).min(forest.ray(pos, dir).count())
     }
 
-    fn scenic_score(forest: &Forest, xs: usize, ys: usize, x: usize, y: usize) -> BoxResult<usize> {
-        Ok(Self::visible_trees(forest, xs, ys, x, y, Dir::Up)?
-            * Self::visible_trees(forest, xs, ys, x, y, Dir::Down)?
-            * Self::visible_trees(forest, xs, ys, x, y, Dir::Left)?
-            * Self::visible_trees(forest, xs, ys, x, y, Dir::Right)?)
+    fn scenic_score(forest: &Grid<u8>, pos: Pos) -> usize {
+        ORTHOGONAL
+            .into_iter()
+            .map(|dir| Self::visible_trees(forest, pos, dir))
+            .product()
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let forest = Utils::byte_matrix(input)?;
-        let ys = forest.len();
-        let xs = forest.get(0).ok_or(AocError)?.len();
-        (0..ys)
-            .fold(Ok(vec![]), |v: BoxResult<_>, y| {
-                (0..xs).fold(v, |v, x| {
-                    let mut v = v?;
-                    v.push(Self::scenic_score(&forest, xs, ys, x, y)?);
-                    Ok(v)
-                })
-            })?
-            .into_iter()
+        let forest = timing::parsing(|| Self::parse(input))?;
+        forest
+            .positions()
+            .map(|pos| Self::scenic_score(&forest, pos))
             .max()
-            .ok_or_else(|| AocError.into())
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day08 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
+            .ok_or_else(|| AocError::new(Reason::NoSolution).into())
     }
 
-    #[test]
-    fn part1() {
-        test1(
-            "30373
-25512
-65332
-33549
-35390",
-            21,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day08 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "30373
-25512
-65332
-33549
-35390",
-            8,
-        );
+    /// A square forest `size` trees on a side.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| rng.range(b'0'..=b'9') as char)
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
     }
 }
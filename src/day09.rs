@@ -1,7 +1,9 @@
 use crate::day::*;
+use crate::geometry::*;
+use crate::random::Rng;
+use crate::timing;
 use std::collections::HashSet;
 use std::iter;
-use std::str::FromStr;
 
 pub struct Day09 {}
 
@@ -12,43 +14,27 @@ impl Day for Day09 {
         "09"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
-}
-
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-struct Coord {
-    x: i64,
-    y: i64,
-}
 
-impl Coord {
-    fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
+}
 
-    fn step(self, dir: Dir) -> Self {
-        match dir {
-            Dir::Up => Self::new(self.x, self.y - 1),
-            Dir::Down => Self::new(self.x, self.y + 1),
-            Dir::Left => Self::new(self.x - 1, self.y),
-            Dir::Right => Self::new(self.x + 1, self.y),
-        }
-    }
+type Coord = Point2<i64>;
 
-    fn move_towards(self, target: &Self) -> Self {
-        let xd = target.x - self.x;
-        let yd = target.y - self.y;
-        if xd.abs() <= 1 && yd.abs() <= 1 {
-            self
-        } else {
-            Self::new(self.x + xd.signum(), self.y + yd.signum())
-        }
+// A knot follows the one before it if they no longer touch.
+fn follow(knot: Coord, target: Coord) -> Coord {
+    if knot.chebyshev(&target) <= 1 {
+        knot
+    } else {
+        knot + (target - knot).signum()
     }
 }
 
@@ -79,74 +65,53 @@ impl State {
         }
     }
 
-    fn step(mut self, dir: Dir) -> Result<Self, AocError> {
+    fn step(mut self, dir: Dir) -> BoxResult<Self> {
         let parts = &mut self.rope.parts;
-        let head = parts.get_mut(0).ok_or(AocError)?;
-        //        println!("head from {:?}", head);
-        *head = head.step(dir);
-        //        println!("head to {:?}", head);
+        let head = parts.get_mut(0).with_reason(Reason::OutOfBounds)?;
+        *head = *head + dir;
+        trace!(3, "head to {}", head);
         for i in 1..parts.len() {
-            let target = parts.get(i - 1).ok_or(AocError)?.to_owned();
-            let part = parts.get_mut(i).ok_or(AocError)?;
-            //            println!("part {} from {:?}", i, part);
-            *part = part.move_towards(&target);
-            //            println!("part {} to {:?}", i, part);
+            let target = parts
+                .get(i - 1)
+                .with_reason(Reason::OutOfBounds)?
+                .to_owned();
+            let part = parts.get_mut(i).with_reason(Reason::OutOfBounds)?;
+            *part = follow(*part, target);
+            trace!(3, "part {} to {}", i, part);
         }
-        self.visited.insert(*parts.last().ok_or(AocError)?);
+        self.visited
+            .insert(*parts.last().with_reason(Reason::OutOfBounds)?);
         Ok(self)
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl FromStr for Dir {
-    type Err = AocError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "U" => Dir::Up,
-            "D" => Dir::Down,
-            "L" => Dir::Left,
-            "R" => Dir::Right,
-            _ => Err(AocError)?,
-        })
-    }
-}
-
 impl Day09 {
     fn process(input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
-            .map(|r| {
-                let r: BoxResult<_> = r.map_err(|e| e.into());
-                r
-            })
-            .map(|l| {
-                l.and_then(|l| {
-                    let t = l
+        let motions = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .enumerate()
+                .map(|(i, l)| {
+                    let l = l?;
+                    let (dir, count) = l
                         .split_whitespace()
                         .collect_tuple::<(_, _)>()
-                        .ok_or_else(|| AocError.into())
-                        .and_then(|(dir, count)| {
-                            Ok((dir.parse::<Dir>()?, count.parse::<usize>()?))
-                        });
-                    t
-                })
-            })
-            .fold(Ok(State::new(n)), |state, motion| {
-                motion.and_then(|motion| {
-                    //println!("{:?}", motion);
-                    let (dir, count) = motion;
-                    iter::repeat(dir).take(count).fold(state, |state, dir| {
-                        state.and_then(|state| state.step(dir).map_err(|e| e.into()))
-                    })
+                        .with_reason(Reason::MissingField)
+                        .at_line(i + 1)
+                        .with_token(&l)?;
+                    Ok((
+                        dir.parse::<Dir>().at_line(i + 1).with_token(dir)?,
+                        count.parse::<usize>().at_line(i + 1).with_token(count)?,
+                    ))
                 })
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        motions
+            .into_iter()
+            .try_fold(State::new(n), |state, motion| {
+                trace!(2, "{:?}", motion);
+                let (dir, count) = motion;
+                iter::repeat_n(dir, count).try_fold(state, |state, dir| state.step(dir))
             })
             .map(|state| state.visited.len())
     }
@@ -158,58 +123,17 @@ impl Day09 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::process(input, 10)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day09 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2",
-            13,
-        );
-    }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day09 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2",
-            1,
-        );
-        test2(
-            "R 5
-U 8
-L 8
-D 3
-R 17
-D 10
-L 25
-U 20",
-            36,
-        );
+    /// `size` motions of the head.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{} {}\n",
+                    rng.pick(&["U", "D", "L", "R"]),
+                    rng.range(1..=20)
+                )
+            })
+            .collect()
     }
 }
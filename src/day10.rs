@@ -1,4 +1,6 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 
 pub struct Day10 {}
 
@@ -10,12 +12,16 @@ impl Day for Day10 {
         "10"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:#?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -32,53 +38,53 @@ impl Day10 {
         T: Sized,
         F: Fn(usize, i64, T) -> Result<T, AocError>,
     {
-        io::BufReader::new(input)
-            .lines()
-            .map(|r| {
-                let r: BoxResult<_> = r.map_err(|e| e.into());
-                r
-            })
-            .fold(
-                Ok(State::<T> {
-                    x: 1,
-                    clk: 1,
-                    acc: init,
-                }),
-                |state, insn| match insn {
-                    Ok(insn) => {
-                        if let Ok(State::<T> {
-                            mut x,
-                            mut clk,
-                            mut acc,
-                        }) = state
-                        {
-                            if at_cycle_start {
-                                acc = f(clk, x, acc)?;
-                            }
-                            let mut tokens = insn.split_whitespace();
-                            match tokens.next().ok_or(AocError)? {
-                                "noop" => clk += 1,
-                                "addx" => {
-                                    clk += 1;
-                                    acc = f(clk, x, acc)?;
-                                    clk += 1;
-                                    let op = tokens.next().ok_or(AocError)?.parse::<Output1>()?;
-                                    x += op;
-                                }
-                                _ => Err(AocError)?,
-                            }
-                            if !at_cycle_start {
-                                acc = f(clk, x, acc)?;
-                            }
-                            Ok(State::<T> { x, clk, acc })
-                        } else {
-                            state
-                        }
+        let program = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .map(|insn| {
+                    let insn = insn?;
+                    let mut tokens = insn.split_whitespace();
+                    match tokens.next().with_reason(Reason::MissingField)? {
+                        "noop" => Ok(None),
+                        "addx" => Ok(Some(
+                            tokens
+                                .next()
+                                .with_reason(Reason::MissingField)?
+                                .parse::<Output1>()?,
+                        )),
+                        other => Err(AocError::new(Reason::Parse).token(other))?,
                     }
-                    Err(e) => Err::<State<T>, _>(e),
-                },
-            )
-            .map(|state| state.acc)
+                })
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        let state = program.into_iter().try_fold(
+            State::<T> {
+                x: 1,
+                clk: 1,
+                acc: init,
+            },
+            |State::<T> {
+                 mut x,
+                 mut clk,
+                 mut acc,
+             },
+             insn| {
+                if at_cycle_start {
+                    acc = f(clk, x, acc)?;
+                }
+                clk += 1;
+                if let Some(op) = insn {
+                    acc = f(clk, x, acc)?;
+                    clk += 1;
+                    x += op;
+                }
+                if !at_cycle_start {
+                    acc = f(clk, x, acc)?;
+                }
+                Ok::<_, AocError>(State::<T> { x, clk, acc })
+            },
+        )?;
+        Ok(state.acc)
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output1> {
@@ -96,7 +102,9 @@ impl Day10 {
         fn draw(clk: usize, x: Output1, mut acc: Output2) -> Result<Output2, AocError> {
             let (i, off) = ((clk - 1) / 40, (clk - 1) % 40);
             let sprite = (x - 1)..=(x + 1);
-            let s = acc.get_mut(i).ok_or(AocError)?;
+            let s = acc
+                .get_mut(i)
+                .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
             s.push(if sprite.contains(&(off as i64)) {
                 '#' // XXX Better contrast with '█'
             } else {
@@ -106,330 +114,25 @@ impl Day10 {
         }
         Self::process(input, draw, vec![String::new(); 6], true)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output1) {
-        assert_eq!(Day10 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
 
-    #[test]
-    fn part1() {
-        test1(
-            "addx 15
-addx -11
-addx 6
-addx -3
-addx 5
-addx -1
-addx -8
-addx 13
-addx 4
-noop
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx -35
-addx 1
-addx 24
-addx -19
-addx 1
-addx 16
-addx -11
-noop
-noop
-addx 21
-addx -15
-noop
-noop
-addx -3
-addx 9
-addx 1
-addx -3
-addx 8
-addx 1
-addx 5
-noop
-noop
-noop
-noop
-noop
-addx -36
-noop
-addx 1
-addx 7
-noop
-noop
-noop
-addx 2
-addx 6
-noop
-noop
-noop
-noop
-noop
-addx 1
-noop
-noop
-addx 7
-addx 1
-noop
-addx -13
-addx 13
-addx 7
-noop
-addx 1
-addx -33
-noop
-noop
-noop
-addx 2
-noop
-noop
-noop
-addx 8
-noop
-addx -1
-addx 2
-addx 1
-noop
-addx 17
-addx -9
-addx 1
-addx 1
-addx -3
-addx 11
-noop
-noop
-addx 1
-noop
-addx 1
-noop
-noop
-addx -13
-addx -19
-addx 1
-addx 3
-addx 26
-addx -30
-addx 12
-addx -1
-addx 3
-addx 1
-noop
-noop
-noop
-addx -9
-addx 18
-addx 1
-addx 2
-noop
-noop
-addx 9
-noop
-noop
-noop
-addx -1
-addx 2
-addx -37
-addx 1
-addx 3
-noop
-addx 15
-addx -21
-addx 22
-addx -6
-addx 1
-noop
-addx 2
-addx 1
-noop
-addx -10
-noop
-noop
-addx 20
-addx 1
-addx 2
-addx 2
-addx -6
-addx -11
-noop
-noop
-noop",
-            13140,
-        );
-    }
-
-    fn test2(s: &str, f: Output2) {
-        assert_eq!(Day10 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "addx 15
-addx -11
-addx 6
-addx -3
-addx 5
-addx -1
-addx -8
-addx 13
-addx 4
-noop
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx 5
-addx -1
-addx -35
-addx 1
-addx 24
-addx -19
-addx 1
-addx 16
-addx -11
-noop
-noop
-addx 21
-addx -15
-noop
-noop
-addx -3
-addx 9
-addx 1
-addx -3
-addx 8
-addx 1
-addx 5
-noop
-noop
-noop
-noop
-noop
-addx -36
-noop
-addx 1
-addx 7
-noop
-noop
-noop
-addx 2
-addx 6
-noop
-noop
-noop
-noop
-noop
-addx 1
-noop
-noop
-addx 7
-addx 1
-noop
-addx -13
-addx 13
-addx 7
-noop
-addx 1
-addx -33
-noop
-noop
-noop
-addx 2
-noop
-noop
-noop
-addx 8
-noop
-addx -1
-addx 2
-addx 1
-noop
-addx 17
-addx -9
-addx 1
-addx 1
-addx -3
-addx 11
-noop
-noop
-addx 1
-noop
-addx 1
-noop
-noop
-addx -13
-addx -19
-addx 1
-addx 3
-addx 26
-addx -30
-addx 12
-addx -1
-addx 3
-addx 1
-noop
-noop
-noop
-addx -9
-addx 18
-addx 1
-addx 2
-noop
-noop
-addx 9
-noop
-noop
-noop
-addx -1
-addx 2
-addx -37
-addx 1
-addx 3
-noop
-addx 15
-addx -21
-addx 22
-addx -6
-addx 1
-noop
-addx 2
-addx 1
-noop
-addx -10
-noop
-noop
-addx 20
-addx 1
-addx 2
-addx 2
-addx -6
-addx -11
-noop
-noop
-noop",
-            vec![
-                "##..##..##..##..##..##..##..##..##..##..".to_string(),
-                "###...###...###...###...###...###...###.".to_string(),
-                "####....####....####....####....####....".to_string(),
-                "#####.....#####.....#####.....#####.....".to_string(),
-                "######......######......######......####".to_string(),
-                "#######.......#######.......#######.....".to_string(),
-            ],
-        );
+    /// A program running for exactly the 240 cycles of the screen, whatever
+    /// `size`, that keeps the sprite near the screen.
+    fn generate_impl(rng: &mut Rng, _size: usize) -> String {
+        let (mut s, mut x, mut cycles) = (String::new(), 1, 0);
+        while cycles < 240 {
+            if cycles + 2 <= 240 && rng.chance(0.6) {
+                let mut v = rng.range(1..=12) * *rng.pick(&[-1, 1]);
+                if !(-1..=40).contains(&(x + v)) {
+                    v = -v;
+                }
+                x += v;
+                cycles += 2;
+                s += &format!("addx {}\n", v);
+            } else {
+                cycles += 1;
+                s += "noop\n";
+            }
+        }
+        s
     }
 }
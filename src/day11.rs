@@ -1,6 +1,7 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::fmt::{Debug, Display};
-use std::io::Read;
 use std::ops::{Add, Div, Mul, Rem};
 use std::str::FromStr;
 
@@ -13,12 +14,34 @@ impl Day for Day11 {
         "11"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn params(&self) -> Params {
+        Params::new()
+            .with("part1.relief", "3")
+            .with("part2.relief", "1")
+            .with("part1.rounds", "20")
+            .with("part2.rounds", "10000")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("relief")
+            .and_then(|relief| Ok((relief, params.get("rounds")?)))
+            .and_then(|(relief, rounds)| self.part1_impl(&mut *input(), relief, rounds))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("relief")
+            .and_then(|relief| Ok((relief, params.get("rounds")?)))
+            .and_then(|(relief, rounds)| self.part2_impl(&mut *input(), relief, rounds))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -67,12 +90,12 @@ where
         let lhs = match tokens.first() {
             Some(&"old") => item,
             Some(s) => s.parse()?,
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::MissingField))?,
         };
         let rhs = match tokens.get(2) {
             Some(&"old") => item,
             Some(s) => s.parse()?,
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::MissingField))?,
         };
         match tokens.get(1) {
             Some(&"+") => Ok(Item {
@@ -81,7 +104,7 @@ where
             Some(&"*") => Ok(Item {
                 level: lhs.level * rhs.level,
             }),
-            _ => Err(AocError.into()),
+            _ => Err(AocError::new(Reason::Parse).token(&self.operation).into()),
         }
     }
 
@@ -111,47 +134,25 @@ where
     type Err = Box<dyn error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.split('\n');
-        let _no = lines
-            .next()
-            .ok_or(AocError)?
-            .split_whitespace()
-            .last()
-            .ok_or(AocError)?
-            .trim_end_matches(':')
-            .parse::<usize>()?;
-        let items = lines
-            .next()
-            .ok_or(AocError)?
-            .strip_prefix("  Starting items: ")
-            .ok_or(AocError)?
+        let mut lines = s.split('\n').zip(1..);
+        let (i, no) = field(&mut lines, "Monkey ")?;
+        no.trim_end_matches(':')
+            .parse::<usize>()
+            .at_line(i)
+            .with_token(no)?;
+        let (i, items) = field(&mut lines, "  Starting items: ")?;
+        let items = items
             .split(", ")
-            .map(|s| s.parse::<Item<T>>().map_err(|e| e.into()))
+            .map(|s| s.parse::<Item<T>>().at_line(i).with_token(s))
             .collect::<BoxResult<Vec<_>>>()?;
-        let operation = lines
-            .next()
-            .ok_or(AocError)?
-            .strip_prefix("  Operation: new = ")
-            .ok_or(AocError)?
-            .to_owned();
-        let divisor = lines
-            .next()
-            .ok_or(AocError)?
-            .strip_prefix("  Test: divisible by ")
-            .ok_or(AocError)?
-            .parse::<T>()?;
-        let true_target = lines
-            .next()
-            .ok_or(AocError)?
-            .strip_prefix("    If true: throw to monkey ")
-            .ok_or(AocError)?
-            .parse::<usize>()?;
-        let false_target = lines
-            .next()
-            .ok_or(AocError)?
-            .strip_prefix("    If false: throw to monkey ")
-            .ok_or(AocError)?
-            .parse::<usize>()?;
+        let (_, operation) = field(&mut lines, "  Operation: new = ")?;
+        let operation = operation.to_owned();
+        let (i, divisor) = field(&mut lines, "  Test: divisible by ")?;
+        let divisor = divisor.parse::<T>().at_line(i).with_token(divisor)?;
+        let (i, target) = field(&mut lines, "    If true: throw to monkey ")?;
+        let true_target = target.parse::<usize>().at_line(i).with_token(target)?;
+        let (i, target) = field(&mut lines, "    If false: throw to monkey ")?;
+        let false_target = target.parse::<usize>().at_line(i).with_token(target)?;
         Ok(Self {
             items,
             operation,
@@ -163,20 +164,56 @@ where
     }
 }
 
+/// The rest of the next line of a monkey's notes after `prefix`, with its
+/// line number within the notes.
+fn field<'a>(
+    lines: &mut impl Iterator<Item = (&'a str, usize)>,
+    prefix: &str,
+) -> BoxResult<(usize, &'a str)> {
+    let (line, i) = lines.next().with_reason(Reason::MissingField)?;
+    let rest = line
+        .strip_prefix(prefix)
+        .with_reason(Reason::Parse)
+        .at_line(i)
+        .with_token(line)?;
+    Ok((i, rest))
+}
+
 #[derive(Debug)]
 struct Monkeys<T>(Vec<Monkey<T>>);
 
-impl<T: FromStr> FromStr for Monkeys<T>
+impl<T: FromStr> Monkeys<T>
 where
     <T as FromStr>::Err: error::Error + 'static,
 {
-    type Err = Box<dyn error::Error>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split("\n\n")
-            .map(|s| s.parse::<Monkey<T>>())
-            .collect::<Result<Vec<_>, _>>()
-            .map(|v| Self(v))
+    // The notes are read a monkey at a time, rather than all at once.
+    fn read(input: &mut dyn io::Read) -> BoxResult<Self> {
+        let mut monkeys = vec![];
+        let mut notes = String::new();
+        let mut start = 1;
+        let lines = io::BufReader::new(input).lines().chain([Ok(String::new())]);
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if !line.is_empty() {
+                if notes.is_empty() {
+                    start = i + 1;
+                }
+                notes.push_str(&line);
+                notes.push('\n');
+            } else if !notes.is_empty() {
+                // Number the lines within the input rather than the notes.
+                let monkey = notes.parse::<Monkey<T>>().with_context(|e| {
+                    let line = start + e.line.unwrap_or(1) - 1;
+                    e.line(line)
+                })?;
+                monkeys.push(monkey);
+                notes.clear();
+            }
+        }
+        if monkeys.is_empty() {
+            Err(AocError::new(Reason::MissingField))?
+        }
+        Ok(Self(monkeys))
     }
 }
 
@@ -204,15 +241,18 @@ where
         <T as FromStr>::Err: error::Error + 'static,
     {
         for source in 0..self.0.len() {
-            let moves = self.get(source).ok_or(AocError)?.inspect(relief, modulus)?;
+            let moves = self
+                .get(source)
+                .with_reason(Reason::OutOfBounds)?
+                .inspect(relief, modulus)?;
             for (item, target) in moves {
                 {
-                    let target = self.get_mut(target).ok_or(AocError)?;
+                    let target = self.get_mut(target).with_reason(Reason::OutOfBounds)?;
                     target.items.push(item);
                 }
-                self.get_mut(source).ok_or(AocError)?.count += 1;
+                self.get_mut(source).with_reason(Reason::OutOfBounds)?.count += 1;
             }
-            self.get_mut(source).ok_or(AocError)?.items = vec![];
+            self.get_mut(source).with_reason(Reason::OutOfBounds)?.items = vec![];
         }
         Ok(())
     }
@@ -223,13 +263,10 @@ where
 }
 
 impl Day11 {
-    fn process<T: FromStr + Debug>(
-        input: &mut dyn io::Read,
-        relief: T,
-        round_count: usize,
-    ) -> BoxResult<Output>
+    fn process<T>(input: &mut dyn io::Read, relief: T, round_count: usize) -> BoxResult<Output>
     where
         T: Copy
+            + Debug
             + FromStr
             + Display
             + Add<Output = T>
@@ -239,11 +276,7 @@ impl Day11 {
             + PartialEq<usize>,
         <T as FromStr>::Err: error::Error + 'static,
     {
-        let mut monkeys = io::BufReader::new(input)
-            .bytes()
-            .map(|b| b.map(|b| b as char))
-            .collect::<Result<String, _>>()?
-            .parse::<Monkeys<T>>()?;
+        let mut monkeys = timing::parsing(|| Monkeys::<T>::read(input))?;
         let modulus = monkeys
             .0
             .iter()
@@ -261,92 +294,55 @@ impl Day11 {
             .product())
     }
 
-    fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        Self::process::<usize>(input, 3, 20)
-    }
-
-    fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        Self::process::<usize>(input, 1, 10000)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day11 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "Monkey 0:
-  Starting items: 79, 98
-  Operation: new = old * 19
-  Test: divisible by 23
-    If true: throw to monkey 2
-    If false: throw to monkey 3
-
-Monkey 1:
-  Starting items: 54, 65, 75, 74
-  Operation: new = old + 6
-  Test: divisible by 19
-    If true: throw to monkey 2
-    If false: throw to monkey 0
-
-Monkey 2:
-  Starting items: 79, 60, 97
-  Operation: new = old * old
-  Test: divisible by 13
-    If true: throw to monkey 1
-    If false: throw to monkey 3
-
-Monkey 3:
-  Starting items: 74
-  Operation: new = old + 3
-  Test: divisible by 17
-    If true: throw to monkey 0
-    If false: throw to monkey 1",
-            10605,
-        );
+    fn part1_impl(
+        &self,
+        input: &mut dyn io::Read,
+        relief: usize,
+        rounds: usize,
+    ) -> BoxResult<Output> {
+        Self::process::<usize>(input, relief, rounds)
     }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day11 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
+    fn part2_impl(
+        &self,
+        input: &mut dyn io::Read,
+        relief: usize,
+        rounds: usize,
+    ) -> BoxResult<Output> {
+        Self::process::<usize>(input, relief, rounds)
     }
 
-    #[test]
-    fn part2() {
-        test2(
-            "Monkey 0:
-  Starting items: 79, 98
-  Operation: new = old * 19
-  Test: divisible by 23
-    If true: throw to monkey 2
-    If false: throw to monkey 3
-
-Monkey 1:
-  Starting items: 54, 65, 75, 74
-  Operation: new = old + 6
-  Test: divisible by 19
-    If true: throw to monkey 2
-    If false: throw to monkey 0
-
-Monkey 2:
-  Starting items: 79, 60, 97
-  Operation: new = old * old
-  Test: divisible by 13
-    If true: throw to monkey 1
-    If false: throw to monkey 3
-
-Monkey 3:
-  Starting items: 74
-  Operation: new = old + 3
-  Test: divisible by 17
-    If true: throw to monkey 0
-    If false: throw to monkey 1",
-            2713310158,
-        );
+    /// The notes on `size` monkeys, at least two, and at most nine, as the
+    /// product of their divisors, different primes, must fit in a worry
+    /// level squared.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        const PRIMES: [usize; 9] = [2, 3, 5, 7, 11, 13, 17, 19, 23];
+        let count = size.clamp(2, PRIMES.len());
+        let mut divisors = PRIMES[..count].to_vec();
+        rng.shuffle(&mut divisors);
+        let squarer = rng.range(0..=count - 1);
+        (0..count)
+            .map(|i| {
+                let items = (0..rng.range(1..=6))
+                    .map(|_| rng.range(50..=99).to_string())
+                    .join(", ");
+                let operation = if i == squarer {
+                    "old * old".to_owned()
+                } else if rng.chance(0.5) {
+                    format!("old + {}", rng.range(1..=8))
+                } else {
+                    format!("old * {}", rng.range(2..=19))
+                };
+                let mut targets = (0..count).filter(|&j| j != i).collect::<Vec<_>>();
+                rng.shuffle(&mut targets);
+                let (if_true, if_false) = (targets[0], *targets.get(1).unwrap_or(&targets[0]));
+                format!(
+                    "Monkey {}:\n  Starting items: {}\n  Operation: new = {}\n  \
+                     Test: divisible by {}\n    If true: throw to monkey {}\n    \
+                     If false: throw to monkey {}\n",
+                    i, items, operation, divisors[i], if_true, if_false
+                )
+            })
+            .join("\n")
     }
 }
@@ -1,7 +1,9 @@
 use crate::day::*;
-use std::collections::HashSet;
+use crate::grid::*;
+use crate::random::Rng;
+use crate::search;
+use crate::timing;
 use std::io::Read;
-use std::iter;
 
 pub struct Day12 {}
 
@@ -12,242 +14,122 @@ impl Day for Day12 {
         "12"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
-    }
-}
-
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-struct Coord {
-    x: i64,
-    y: i64,
-}
-
-impl Coord {
-    fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn step(self, dir: Dir) -> Self {
-        match dir {
-            Dir::Up => Self::new(self.x, self.y - 1),
-            Dir::Down => Self::new(self.x, self.y + 1),
-            Dir::Left => Self::new(self.x - 1, self.y),
-            Dir::Right => Self::new(self.x + 1, self.y),
-        }
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
 #[derive(Debug)]
 struct Map {
-    area: Vec<Vec<u8>>,
-    x_size: usize,
-    y_size: usize,
-    start: Coord,
-    end: Coord,
+    area: Grid<u8>,
+    start: Pos,
+    end: Pos,
 }
 
 impl Map {
-    fn is_valid(&self, c: Coord) -> bool {
-        c.x >= 0 && c.x < self.x_size as i64 && c.y >= 0 && c.y < self.y_size as i64
-    }
-
     // Report the height of position.
-    fn height(&self, c: Coord) -> Result<u8, AocError> {
-        Ok(
-            match *self
-                .area
-                .get(c.y as usize)
-                .ok_or(AocError)?
-                .get(c.x as usize)
-                .ok_or(AocError)?
-            {
-                b'S' => b'a',
-                b'E' => b'z',
-                h => h,
-            },
-        )
-    }
-
-    // Take a step in the given direction, erroring out if we fall of the map.
-    fn step(&self, c: Coord, dir: Dir) -> Result<Coord, AocError> {
-        let next = c.step(dir);
-        if self.is_valid(next) {
-            Ok(next)
-        } else {
-            Err(AocError)
+    fn height(&self, c: Pos) -> u8 {
+        match self.area[c] {
+            b'S' => b'a',
+            b'E' => b'z',
+            h => h,
         }
     }
 
-    // Find all moves that remains on the map, dosen't take us to where we've
-    // already been, and isn't more than one unit higher than the current
-    // position.
-    fn candidates(&self, c: Coord, visited: &HashSet<Coord>) -> HashSet<Coord> {
-        [Dir::Up, Dir::Down, Dir::Left, Dir::Right]
-            .into_iter()
-            .flat_map(|dir| self.step(c, dir).ok())
-            .filter(|pos| !visited.contains(pos))
-            .filter(|pos| self.height(*pos).unwrap() <= self.height(c).unwrap() + 1) // XXX unwrap
-            .collect()
+    // The positions next to c that are at most one unit higher, i.e. those
+    // that can be climbed to from it.
+    fn climbs(&self, c: Pos) -> impl Iterator<Item = Pos> + '_ {
+        let h = self.height(c);
+        self.area
+            .neighbours4(c)
+            .filter(move |&next| self.height(next) <= h + 1)
     }
 
-    fn find_starts(&self) -> BoxResult<HashSet<Coord>> {
-        (0..self.y_size as i64).fold(Ok(HashSet::new()), |starts: BoxResult<_>, y| {
-            (0..self.x_size as i64).fold(starts, |starts, x| {
-                let c = Coord::new(x, y);
-                let mut starts = starts?;
-                if self.height(c)? == b'a' {
-                    starts.insert(c);
-                }
-                Ok(starts)
-            })
-        })
+    // The positions next to c that can be climbed from to it.
+    fn descents(&self, c: Pos) -> impl Iterator<Item = Pos> + '_ {
+        let h = self.height(c);
+        self.area
+            .neighbours4(c)
+            .filter(move |&next| h <= self.height(next) + 1)
     }
 }
 
 impl Day12 {
-    fn step_count(map: &Map, start: Coord, limit: Option<Output>) -> BoxResult<Output> {
-        match (0..)
-            .take_while(|i| {
-                if let Some(limit) = limit {
-                    *i < limit
-                } else {
-                    true
-                }
-            })
-            .try_fold(
-                Ok((
-                    iter::once(start).collect::<HashSet<_>>(),
-                    iter::once(start).collect::<HashSet<_>>(),
-                )),
-                |ctx, _i| {
-                    // println!("{}", _i);
-                    let (pos, mut visited) = ctx?;
-                    let next = pos
-                        .into_iter()
-                        .flat_map(|pos| map.candidates(pos, &visited))
-                        .collect::<HashSet<_>>();
-                    for pos in next.iter() {
-                        visited.insert(*pos);
-                    }
-                    if next.contains(&map.end) {
-                        Err(Ok(_i + 1))
-                    } else {
-                        Ok(Ok((next, visited)))
-                    }
-                },
-            ) {
-            Err(rv) => rv,
-            Ok(_) => Ok(limit.unwrap()), // XXX unwrap
-        }
-    }
-
     fn parse(input: &mut dyn Read) -> BoxResult<Map> {
-        let area = Utils::byte_matrix(input)?;
-        let y_size = area.len();
-        let x_size = area.get(0).ok_or(AocError)?.len();
-        let (start, end) = (0..y_size as i64).fold(Ok((None, None)), |ctx: BoxResult<_>, y| {
-            (0..x_size as i64).fold(ctx, |ctx, x| {
-                if let Ok((start, end)) = ctx {
-                    Ok(
-                        match area
-                            .get(y as usize)
-                            .ok_or(AocError)?
-                            .get(x as usize)
-                            .ok_or(AocError)?
-                        {
-                            b'S' => (Some(Coord::new(x, y)), end),
-                            b'E' => (start, Some(Coord::new(x, y))),
-                            _ => (start, end),
-                        },
-                    )
-                } else {
-                    ctx
-                }
-            })
+        let area = Grid::parse(input, |c| {
+            (c.is_ascii_lowercase() || c == 'S' || c == 'E').then_some(c as u8)
         })?;
-        let map = Map {
-            area,
-            x_size,
-            y_size,
-            start: start.ok_or(AocError)?,
-            end: end.ok_or(AocError)?,
-        };
-        Ok(map)
+        let start = area
+            .position(|&h| h == b'S')
+            .with_reason(Reason::MissingField)
+            .with_token("S")?;
+        let end = area
+            .position(|&h| h == b'E')
+            .with_reason(Reason::MissingField)
+            .with_token("E")?;
+        Ok(Map { area, start, end })
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let map = Self::parse(input)?;
-        Self::step_count(&map, map.start, None)
+        let map = timing::parsing(|| Self::parse(input))?;
+        search::bfs(map.start, |&c| map.climbs(c), |&c| c == map.end)
+            .map(|path| path.cost)
+            .with_reason(Reason::NoSolution)
     }
 
+    // Search backwards, from the end to the closest lowest position.
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let map = Self::parse(input)?;
-        // XXX This limit should rather be maintained on the fly, by use of
-        // XXX folding.
-        let limit = Self::step_count(&map, map.start, None)?;
-        let starts = map.find_starts()?;
-        starts
-            .into_iter()
-            .map(|start| Self::step_count(&map, start, Some(limit)))
-            .fold(Ok(None), |min, steps| {
-                if let Ok(min) = min {
-                    steps.map(|steps| {
-                        Some(min.map_or(steps, |min| if steps < min { steps } else { min }))
-                    })
+        let map = timing::parsing(|| Self::parse(input))?;
+        search::bfs(map.end, |&c| map.descents(c), |&c| map.height(c) == b'a')
+            .map(|path| path.cost)
+            .with_reason(Reason::NoSolution)
+    }
+
+    /// A heightmap `size` rows high, at least five, and four times as wide,
+    /// climbing along the branches of a random spanning tree towards the
+    /// best signal at its root, so that the start, at its deepest leaf, can
+    /// reach it.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let (height, width) = (size.max(5), size.max(5) * 4);
+        loop {
+            let mut depth = Grid::new(width, height, None);
+            let end = (rng.range(0..=height - 1), rng.range(0..=width - 1));
+            depth[end] = Some(0);
+            let mut stack = vec![end];
+            while let Some(&pos) = stack.last() {
+                let d = depth[pos].unwrap_or(0);
+                let unvisited = depth
+                    .neighbours4(pos)
+                    .filter(|&next| depth[next].is_none())
+                    .collect::<Vec<_>>();
+                if unvisited.is_empty() {
+                    stack.pop();
                 } else {
-                    min
+                    let next = *rng.pick(&unvisited);
+                    depth[next] = Some(d + 1);
+                    stack.push(next);
                 }
-            })
-            .map(|min| min.unwrap()) // XXX unwrap
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day12 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "Sabqponm
-abcryxxl
-accszExk
-acctuvwj
-abdefghi",
-            31,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day12 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "Sabqponm
-abcryxxl
-accszExk
-acctuvwj
-abdefghi",
-            29,
-        );
+            }
+            let depth = depth.map(|d| d.unwrap_or(0));
+            let (start, &deepest) = depth.iter().max_by_key(|&(_, d)| d).unwrap_or((end, &0));
+            // A step towards the root rises at most one, and the root's
+            // neighbours are high enough to climb to it from.
+            if deepest < 25 {
+                continue;
+            }
+            let k = (deepest - 1) / 24;
+            let mut map = depth.map(|&d| (b'a' + ((deepest - d) / k).min(25) as u8) as char);
+            map[start] = 'S';
+            map[end] = 'E';
+            return map.to_string();
+        }
     }
 }
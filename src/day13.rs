@@ -1,7 +1,10 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::iter;
+use std::str::FromStr;
 
 pub struct Day13 {}
 
@@ -12,17 +15,23 @@ impl Day for Day13 {
         "13"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
+/// A packet: an integer or a list of packets, ordered by the distress signal
+/// rules.
 #[derive(Debug, Eq)]
-enum Value {
+pub enum Value {
     Int(u8),
     List(Vec<Value>),
 }
@@ -71,10 +80,19 @@ impl PartialOrd for Value {
     }
 }
 
+impl FromStr for Value {
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Day13::parse(s)
+    }
+}
+
 impl Day13 {
     fn parse(s: &str) -> BoxResult<Value> {
         s.bytes()
-            .fold(Ok((vec![], vec![], None)), |ctx, c| {
+            .enumerate()
+            .fold(Ok((vec![], vec![], None)), |ctx, (i, c)| {
                 let (mut v, mut stack, mut d) = ctx?;
                 match c {
                     b'[' => {
@@ -100,29 +118,36 @@ impl Day13 {
                             d = None;
                         }
                         let list = Value::List(v);
-                        v = stack.pop().ok_or(AocError)?;
+                        v = stack.pop().with_reason(Reason::Parse).at_column(i + 1)?;
                         v.push(list);
                     }
-                    _ => Err(AocError)?,
+                    _ => Err(AocError::new(Reason::Parse)
+                        .column(i + 1)
+                        .token(&(c as char)))?,
                 }
                 Ok((v, stack, d))
             })
-            .and_then(|(v, _, _)| v.into_iter().next().ok_or_else(|| AocError.into()))
+            .and_then(|(v, _, _)| v.into_iter().next().with_reason(Reason::Parse))
     }
 
     fn process(input: &mut dyn io::Read) -> BoxResult<Output> {
-        Ok(io::BufReader::new(input)
-            .lines()
-            .group_by(|r| r.as_ref().map_or(false, |s| s.is_empty()))
-            .into_iter()
-            .filter(|&(is_blank, _)| !is_blank)
-            .map(|(_, mut pair)| {
-                let a = Self::parse(&pair.next().ok_or(AocError)??)?;
-                let b = Self::parse(&pair.next().ok_or(AocError)??)?;
-                Ok(a.cmp(&b)) as BoxResult<_>
-            })
+        let pairs = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .group_by(|r| r.as_ref().is_ok_and(|s| s.is_empty()))
+                .into_iter()
+                .filter(|&(is_blank, _)| !is_blank)
+                .map(|(_, mut pair)| {
+                    let a = Self::parse(&pair.next().with_reason(Reason::MissingField)??)?;
+                    let b = Self::parse(&pair.next().with_reason(Reason::MissingField)??)?;
+                    Ok((a, b))
+                })
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        Ok(pairs
+            .iter()
             .enumerate()
-            .filter(|(_, ord)| ord.as_ref().map_or(true, |ord| *ord == Ordering::Less))
+            .filter(|(_, (a, b))| a.cmp(b) == Ordering::Less)
             .map(|(i, _)| i + 1)
             .sum::<Output>())
     }
@@ -132,14 +157,16 @@ impl Day13 {
     }
 
     fn process2(input: &mut dyn io::Read) -> BoxResult<Output> {
-        let mut packets = io::BufReader::new(input)
-            .lines()
-            .map(|r| r.map_err(|e| e.into()))
-            .filter(|l| l.as_ref().map_or(true, |s| !s.is_empty()))
-            .chain(iter::once(Ok("[[2]]".to_string())))
-            .chain(iter::once(Ok("[[6]]".to_string())))
-            .map(|packet| packet.and_then(|p| Self::parse(&p)))
-            .collect::<BoxResult<Vec<_>>>()?;
+        let mut packets = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .map(|r| r.map_err(|e| e.into()))
+                .filter(|l| l.as_ref().map_or(true, |s| !s.is_empty()))
+                .chain(iter::once(Ok("[[2]]".to_string())))
+                .chain(iter::once(Ok("[[6]]".to_string())))
+                .map(|packet| packet.and_then(|p| Self::parse(&p)))
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
         packets.sort();
         Ok(packets
             .iter()
@@ -160,77 +187,32 @@ impl Day13 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::process2(input)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day13 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "[1,1,3,1,1]
-[1,1,5,1,1]
-
-[[1],[2,3,4]]
-[[1],4]
-
-[9]
-[[8,7,6]]
-
-[[4,4],4,4]
-[[4,4],4,4,4]
 
-[7,7,7,7]
-[7,7,7]
-
-[]
-[3]
-
-[[[]]]
-[[]]
-
-[1,[2,[3,[4,[5,6,7]]]],8,9]
-[1,[2,[3,[4,[5,6,0]]]],8,9]",
-            13,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day13 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "[1,1,3,1,1]
-[1,1,5,1,1]
-
-[[1],[2,3,4]]
-[[1],4]
-
-[9]
-[[8,7,6]]
-
-[[4,4],4,4]
-[[4,4],4,4,4]
-
-[7,7,7,7]
-[7,7,7]
-
-[]
-[3]
-
-[[[]]]
-[[]]
-
-[1,[2,[3,[4,[5,6,7]]]],8,9]
-[1,[2,[3,[4,[5,6,0]]]],8,9]",
-            140,
-        );
+    /// A packet of lists nested at most `depth` deep.
+    fn random_packet(rng: &mut Rng, depth: usize) -> Value {
+        Value::List(
+            (0..rng.range(0..=4))
+                .map(|_| {
+                    if depth > 1 && rng.chance(0.3) {
+                        Self::random_packet(rng, depth - 1)
+                    } else {
+                        Value::Int(rng.range(0..=10))
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// `size` pairs of packets.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{}\n{}\n",
+                    Self::random_packet(rng, 4),
+                    Self::random_packet(rng, 4)
+                )
+            })
+            .join("\n")
     }
 }
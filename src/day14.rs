@@ -1,7 +1,10 @@
 use crate::day::*;
+use crate::geometry::Point2;
+use crate::random::Rng;
+use crate::timing;
+use crate::visual;
 use std::cmp::{max, min};
 use std::collections::HashMap;
-use std::str::FromStr;
 
 pub struct Day14 {}
 
@@ -12,42 +15,20 @@ impl Day for Day14 {
         "14"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
-}
-
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-struct Coord {
-    x: usize,
-    y: usize,
-}
 
-impl Coord {
-    const fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
-impl FromStr for Coord {
-    type Err = Box<dyn error::Error>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut tokens = s.split(',');
-        let rv = Ok(Self {
-            x: tokens.next().ok_or(AocError)?.parse()?,
-            y: tokens.next().ok_or(AocError)?.parse()?,
-        });
-        if tokens.next().is_some() {
-            Err(AocError)?;
-        }
-        rv
-    }
-}
+type Coord = Point2<usize>;
 
 enum Thing {
     Rock,
@@ -93,7 +74,7 @@ impl World {
                     }
                 }
             } else {
-                Err(AocError)?
+                Err(AocError::new(Reason::Parse).token(scan))?
             }
         }
         Ok(())
@@ -134,23 +115,55 @@ impl World {
         }
         false
     }
+
+    // The cave around the rocks and the sand, with the source at +.
+    fn render(&self, start: Coord) -> String {
+        let xs = self.map.keys().map(|c| c.x).chain([start.x]);
+        let (left, right) = xs.minmax().into_option().unwrap_or((start.x, start.x));
+        let bottom = self.floor.unwrap_or(self.bottom);
+        let mut s = String::new();
+        for y in 0..=bottom {
+            for x in left.saturating_sub(1)..=right + 1 {
+                let c = Coord::new(x, y);
+                s.push(match self.map.get(&c) {
+                    _ if c == start => '+',
+                    _ if Some(y) == self.floor => '#',
+                    Some(Thing::Rock) => '#',
+                    Some(Thing::Sand) => 'o',
+                    None => '.',
+                });
+            }
+            s.push('\n');
+        }
+        s
+    }
 }
 
+// Pouring a grain is a small step, so a frame is shown every so many.
+const GRAINS_PER_FRAME: usize = 25;
+
 impl Day14 {
     fn process(input: &mut dyn io::Read, start: Coord, has_floor: bool) -> BoxResult<Output> {
         let mut world = World::new();
-        for segments in io::BufReader::new(input).lines() {
-            world.add_segments(&segments?, has_floor)?;
-        }
+        timing::parsing(|| -> BoxResult<_> {
+            for (i, segments) in io::BufReader::new(input).lines().enumerate() {
+                world.add_segments(&segments?, has_floor).at_line(i + 1)?;
+            }
+            Ok(())
+        })?;
         match (0..).try_fold((), |_, i| -> Result<_, BoxResult<_>> {
             if world.pour(start) {
+                if i % GRAINS_PER_FRAME == 0 {
+                    visual::frame(|| world.render(start));
+                }
                 Ok(())
             } else {
+                visual::frame(|| world.render(start));
                 Err(Ok(i))
             }
         }) {
             Err(rv) => rv,
-            _ => Err(AocError.into()),
+            _ => Err(AocError::new(Reason::NoSolution).into()),
         }
     }
 
@@ -161,37 +174,34 @@ impl Day14 {
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
         Self::process(input, Coord::new(500, 0), true)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day14 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
 
-    #[test]
-    fn part1() {
-        test1(
-            "498,4 -> 498,6 -> 496,6
-503,4 -> 502,4 -> 502,9 -> 494,9
-",
-            24,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day14 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "498,4 -> 498,6 -> 496,6
-503,4 -> 502,4 -> 502,9 -> 494,9
-",
-            93,
-        );
+    /// `size` rock paths below and around the source of the sand, each of
+    /// alternating horizontal and vertical lines.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let mut at = Coord::new(rng.range(470..=530), rng.range(13..=160));
+                let mut path = vec![at.to_string()];
+                let horizontal = rng.chance(0.5);
+                for i in 0..rng.range(1..=4) {
+                    let step = rng.range(1..=10);
+                    if (i % 2 == 0) == horizontal {
+                        at.x = if rng.chance(0.5) {
+                            at.x + step
+                        } else {
+                            at.x - step
+                        };
+                    } else {
+                        at.y = if rng.chance(0.5) {
+                            at.y + step
+                        } else {
+                            at.y - step
+                        };
+                    }
+                    path.push(at.to_string());
+                }
+                path.join(" -> ") + "\n"
+            })
+            .collect()
     }
 }
@@ -1,5 +1,10 @@
+use crate::cancel;
 use crate::day::*;
-use std::collections::{HashMap, HashSet};
+use crate::pattern::Pattern;
+use crate::random::Rng;
+use crate::timing;
+use std::cmp::max;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::io::Read;
@@ -16,12 +21,51 @@ impl Day for Day16 {
         "16"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input(), 30));
+    fn params(&self) -> Params {
+        Params::new()
+            .with("part1.minutes", "30")
+            .with("part2.minutes", "26")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input(), 26));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| self.part1_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| self.part2_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
+    }
+
+    fn has_reference(&self, _part: usize) -> bool {
+        true
+    }
+
+    fn reference_params(&self) -> Params {
+        Params::new().with("minutes", "14")
+    }
+
+    fn reference(
+        &self,
+        part: usize,
+        input: &dyn Fn() -> Box<dyn io::Read>,
+        params: &Params,
+    ) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| Self::reference_impl(&mut *input(), part, n))
+            .in_day(self.tag())
+            .into()
     }
 }
 
@@ -32,30 +76,19 @@ struct Valve {
     neighbours: Vec<String>,
 }
 
+const VALVE: Pattern = Pattern::new(
+    "Valve {word} has flow rate={int}; {tunnels lead|tunnel leads} to {valves|valve} {}",
+);
+
 impl FromStr for Valve {
     type Err = Box<dyn error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
-        let mut tokens = s.split_whitespace();
-        let name = tokens.nth(1).ok_or(AocError)?.to_owned();
-        let rate = tokens
-            .nth(2)
-            .ok_or(AocError)?
-            .split('=')
-            .nth(1)
-            .ok_or(AocError)?
-            .strip_suffix(';')
-            .ok_or(AocError)?
-            .parse::<usize>()?;
-        let neighbours = tokens
-            .skip(4)
-            .map(|s| s.trim_end_matches(',').to_owned())
-            .collect::<Vec<_>>();
+        let mut fields = VALVE.fields(s)?;
         Ok(Self {
-            name,
-            rate,
-            neighbours,
+            name: fields.field()?,
+            rate: fields.field()?,
+            neighbours: fields.list(", ")?,
         })
     }
 }
@@ -75,7 +108,8 @@ impl Cave {
         let valves: Vec<Valve> = io::BufReader::new(input)
             .lines()
             .map(|l| l.map_err(|e| e.into()))
-            .map(|l| l.and_then(|l| l.parse::<Valve>()))
+            .enumerate()
+            .map(|(i, l)| l.and_then(|l| l.parse::<Valve>().at_line(i + 1)))
             .collect::<BoxResult<_>>()?;
         assert!(valves.len() <= u8::MAX as usize + 1);
         let name_to_valve: HashMap<String, u8> = valves
@@ -97,9 +131,9 @@ impl Cave {
             .iter()
             .map(|v| {
                 v.neighbours.iter().fold(Ok(0), |set, neighbour| {
-                    set.and_then(
-                        |set| Ok(set | 1 << *name_to_valve.get(neighbour).ok_or(AocError)?),
-                    )
+                    set.and_then(|set| {
+                        Ok(set | 1 << *name_to_valve.get(neighbour).with_token(neighbour)?)
+                    })
                 })
             })
             .collect::<BoxResult<_>>()?;
@@ -108,10 +142,10 @@ impl Cave {
             .filter(|v| v.rate > 0)
             .map(|v| v.rate)
             .collect::<Vec<_>>();
-        // println!("{:?}", name_to_valve);
-        // println!("{:?}", openable);
-        // println!("{:?}", neighbours);
-        // println!("{:?}", rate);
+        trace!(2, "valves {:?}", name_to_valve);
+        trace!(2, "openable {:?}", openable);
+        trace!(2, "neighbours {:?}", neighbours);
+        trace!(2, "rates {:?}", rate);
         Ok(Cave {
             name_to_valve,
             openable,
@@ -130,26 +164,34 @@ impl Cave {
         flow: usize,
         flow_max: &mut usize,
     ) {
+        if cancel::cancelled() {
+            return;
+        }
         if t > eol {
             if flow > *flow_max {
                 *flow_max = flow;
-                // println!("NEW MAX {}", flow);
+                trace!(1, "new max {}", flow);
             }
             return;
         }
         let index = (valve_no as usize) << OPENABLE_COUNT | valves_state as usize;
         let offset = index / 8;
         let visit_mask = 1 << (index % 8) as u16;
+        let delta = (0..NOT_OPENABLE)
+            .filter(|valve_no| valves_state & (1 << valve_no) != 0)
+            .map(|valve_no| self.rate[valve_no as usize])
+            .sum::<usize>();
         if (visited[offset] & visit_mask) == 0 {
             visited[offset] |= visit_mask;
-            let delta = (0..NOT_OPENABLE)
-                .filter(|valve_no| valves_state & (1 << valve_no) != 0)
-                .map(|valve_no| self.rate[valve_no as usize])
-                .sum::<usize>();
-            // println!(
-            //     "t {} valve {} states {} flow {} delta {}",
-            //     t, valve_no, valves_state, flow, delta
-            // );
+            trace!(
+                3,
+                "t {} valve {} states {} flow {} delta {}",
+                t,
+                valve_no,
+                valves_state,
+                flow,
+                delta
+            );
             let flow = flow + delta;
             let openable_valve_no = self.openable[valve_no as usize] as u16;
             if openable_valve_no != NOT_OPENABLE as u16 {
@@ -177,6 +219,12 @@ impl Cave {
                 neighbour += 1;
             }
             visited[offset] &= !visit_mask;
+        } else if flow + delta * (eol + 1 - t) > *flow_max {
+            // Back at a valve this path has been at with the same valves
+            // open, which is no better than having waited there, so wait
+            // until the end instead.
+            *flow_max = flow + delta * (eol + 1 - t);
+            trace!(1, "new max {}", flow_max);
         }
     }
 
@@ -213,20 +261,32 @@ impl Cave {
         flow_max: &mut usize,
     ) {
         *flow_max = states.iter().map(|(_, flow)| *flow).max().unwrap_or(0);
-        //        println!("traverse2 t {} max {}", t, flow_max);
-        if t > eol {
+        trace!(2, "t {} states {} max {}", t, states.len(), flow_max);
+        if t > eol || cancel::cancelled() {
             return;
         }
+        let states = states
+            .into_iter()
+            .map(|(state, flow)| {
+                let delta = (0..self.openable.len())
+                    .filter(|valve_no| (state.valves & (1 << valve_no)) != 0)
+                    .map(|valve_no| self.rate[self.openable[valve_no] as usize])
+                    .sum::<usize>();
+                (state, flow + delta, delta)
+            })
+            .collect::<Vec<_>>();
+        // Prune branches that cannot beat what some other state is sure to
+        // reach by just keeping its currently open valves flowing.
+        let remaining = eol - t;
+        let full_rate = self.rate.iter().sum::<usize>();
+        let floor = states
+            .iter()
+            .map(|(_, flow, delta)| flow + delta * remaining)
+            .max()
+            .unwrap_or(0);
         let mut new_states = HashSet::new();
-        for (state, flow) in states {
-            let delta = (0..self.openable.len())
-                .filter(|valve_no| (state.valves & (1 << valve_no)) != 0)
-                .map(|valve_no| self.rate[self.openable[valve_no] as usize])
-                .sum::<usize>();
-            let flow = flow + delta;
-            // XXX Ugly heuristic to speed up the process by pruning unlikely
-            // XXX branches.
-            if flow + self.rate.iter().sum::<usize>() * (eol - t + 1) < 3000 {
+        for (state, flow, _) in states {
+            if flow + full_rate * remaining < floor {
                 continue;
             }
             for action0 in self.single_action(state.duo.player0(), state) {
@@ -235,7 +295,7 @@ impl Cave {
                     new_states.insert((new, flow));
                     if !seen.contains(&new) {
                         seen.insert(new);
-                        //println!("  {:?} {:?}", action0, action1);
+                        trace!(3, "  {:?} {:?}", action0, action1);
                     }
                 }
             }
@@ -313,10 +373,15 @@ impl Display for Duo {
 
 impl Day16 {
     fn process(input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        let cave = Cave::parse(input)?;
+        let cave = timing::parsing(|| Cave::parse(input))?;
+        let start = *cave
+            .name_to_valve
+            .get("AA")
+            .with_reason(Reason::MissingField)
+            .with_token("AA")?;
         let mut flow_max = 0;
         cave.traverse(
-            *cave.name_to_valve.get("AA").ok_or(AocError)?,
+            start,
             0,
             &mut [0; 64 * (u16::MAX as usize + 1) / 8],
             1,
@@ -324,17 +389,23 @@ impl Day16 {
             0,
             &mut flow_max,
         );
+        cancel::check()?;
         Ok(flow_max)
     }
 
     fn process2(input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        let cave = Cave::parse(input)?;
-        let start = *cave.name_to_valve.get("AA").ok_or(AocError)?;
-        // println!(
-        //     "full_rate {} start {}",
-        //     cave.rate.iter().sum::<Output>(),
-        //     start
-        // );
+        let cave = timing::parsing(|| Cave::parse(input))?;
+        let start = *cave
+            .name_to_valve
+            .get("AA")
+            .with_reason(Reason::MissingField)
+            .with_token("AA")?;
+        trace!(
+            1,
+            "full rate {} start {}",
+            cave.rate.iter().sum::<Output>(),
+            start
+        );
         let mut flow_max = 0;
         cave.traverse2(
             iter::once((
@@ -350,6 +421,7 @@ impl Day16 {
             n,
             &mut flow_max,
         );
+        cancel::check()?;
         Ok(flow_max)
     }
 
@@ -360,53 +432,140 @@ impl Day16 {
     fn part2_impl(&self, input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
         Self::process2(input, n)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn test1(s: &str, n: usize, f: Output) {
-        assert_eq!(Day16 {}.part1_impl(&mut s.as_bytes(), n).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
-Valve BB has flow rate=13; tunnels lead to valves CC, AA
-Valve CC has flow rate=2; tunnels lead to valves DD, BB
-Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
-Valve EE has flow rate=3; tunnels lead to valves FF, DD
-Valve FF has flow rate=0; tunnels lead to valves EE, GG
-Valve GG has flow rate=0; tunnels lead to valves FF, HH
-Valve HH has flow rate=22; tunnel leads to valve GG
-Valve II has flow rate=0; tunnels lead to valves AA, JJ
-Valve JJ has flow rate=21; tunnel leads to valve II",
-            30,
-            1651,
-        );
-    }
-
-    fn test2(s: &str, n: usize, f: Output) {
-        assert_eq!(Day16 {}.part2_impl(&mut s.as_bytes(), n).ok(), Some(f));
+    /// The most pressure released in `n` minutes by as many players as the
+    /// part, trying every move of every player every minute: opening the
+    /// valve at hand, following a tunnel or staying, and keeping the best
+    /// for each place of the players and set of open valves.
+    fn reference_impl(input: &mut dyn io::Read, players: usize, n: usize) -> BoxResult<Output> {
+        let valves = io::BufReader::new(input)
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                l.map_err(|e| e.into())
+                    .and_then(|l| l.parse::<Valve>())
+                    .at_line(i + 1)
+            })
+            .collect::<BoxResult<Vec<_>>>()?;
+        let index = valves
+            .iter()
+            .enumerate()
+            .map(|(i, valve)| (valve.name.as_str(), i))
+            .collect::<HashMap<_, _>>();
+        let tunnels = valves
+            .iter()
+            .map(|valve| {
+                valve
+                    .neighbours
+                    .iter()
+                    .map(|name| index.get(name.as_str()).copied().with_token(name))
+                    .collect::<BoxResult<Vec<_>>>()
+            })
+            .collect::<BoxResult<Vec<_>>>()?;
+        let start = *index
+            .get("AA")
+            .with_reason(Reason::MissingField)
+            .with_token("AA")?;
+        let mut best = HashMap::from([((vec![start; players], BTreeSet::<usize>::new()), 0)]);
+        for _ in 0..n {
+            let mut next = HashMap::new();
+            for ((places, open), released) in best {
+                let released = released + open.iter().map(|&v| valves[v].rate).sum::<usize>();
+                let mut moves = vec![(vec![], open)];
+                for &at in &places {
+                    let mut after = vec![];
+                    for (moved, open) in moves {
+                        let mut with = |to: usize, open: &BTreeSet<usize>| {
+                            let mut moved = moved.to_owned();
+                            moved.push(to);
+                            after.push((moved, open.to_owned()));
+                        };
+                        with(at, &open);
+                        if valves[at].rate > 0 && !open.contains(&at) {
+                            with(at, &open.iter().copied().chain(iter::once(at)).collect());
+                        }
+                        for &to in &tunnels[at] {
+                            with(to, &open);
+                        }
+                    }
+                    moves = after;
+                }
+                for (mut places, open) in moves {
+                    places.sort();
+                    let best = next.entry((places, open)).or_insert(0);
+                    *best = max(*best, released);
+                }
+            }
+            best = next;
+        }
+        best.into_values().max().with_reason(Reason::NoSolution)
     }
 
-    #[test]
-    fn part2() {
-        test2(
-            "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
-Valve BB has flow rate=13; tunnels lead to valves CC, AA
-Valve CC has flow rate=2; tunnels lead to valves DD, BB
-Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
-Valve EE has flow rate=3; tunnels lead to valves FF, DD
-Valve FF has flow rate=0; tunnels lead to valves EE, GG
-Valve GG has flow rate=0; tunnels lead to valves FF, HH
-Valve HH has flow rate=22; tunnel leads to valve GG
-Valve II has flow rate=0; tunnels lead to valves AA, JJ
-Valve JJ has flow rate=21; tunnel leads to valve II",
-            26,
-            1707,
-        );
+    /// A cave of `size` valves, at least two and at most 64, as many as fit
+    /// in a set of neighbours, connected by tunnels and starting at valve AA,
+    /// of which at most 15 can be opened.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let count = size.clamp(2, u64::BITS as usize);
+        let mut names = (b'A'..=b'Z')
+            .cartesian_product(b'A'..=b'Z')
+            .map(|(a, b)| String::from_utf8(vec![a, b]).unwrap())
+            .filter(|name| name != "AA")
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut names);
+        names.truncate(count - 1);
+        names.insert(0, "AA".to_owned());
+        let mut rates = vec![0; count];
+        let mut openable = (1..count).collect::<Vec<_>>();
+        rng.shuffle(&mut openable);
+        for &valve in openable
+            .iter()
+            .take(rng.range(1..=(count / 3).clamp(1, NOT_OPENABLE as usize)))
+        {
+            rates[valve] = rng.range(1..=25);
+        }
+        // A spanning tree, so that every valve can be reached, and a few
+        // more tunnels to make loops.
+        let mut tunnels = (1..count)
+            .map(|valve| (rng.range(0..=valve - 1), valve))
+            .collect::<HashSet<_>>();
+        for _ in 0..count / 4 {
+            let (a, b) = (rng.range(0..=count - 1), rng.range(0..=count - 1));
+            if a != b {
+                tunnels.insert((a.min(b), a.max(b)));
+            }
+        }
+        let mut lines = (0..count)
+            .map(|valve| {
+                let mut neighbours = tunnels
+                    .iter()
+                    .filter_map(|&(a, b)| {
+                        if valve == a {
+                            Some(&names[b])
+                        } else if valve == b {
+                            Some(&names[a])
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                // Sorted first, as the order of a set changes from run to run.
+                neighbours.sort();
+                rng.shuffle(&mut neighbours);
+                let tunnels = if neighbours.len() == 1 {
+                    "tunnel leads to valve"
+                } else {
+                    "tunnels lead to valves"
+                };
+                format!(
+                    "Valve {} has flow rate={}; {} {}\n",
+                    names[valve],
+                    rates[valve],
+                    tunnels,
+                    neighbours.iter().join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut lines);
+        lines.concat()
     }
 }
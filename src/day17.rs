@@ -1,5 +1,9 @@
+use crate::cycle;
 use crate::day::*;
-use std::collections::HashMap;
+use crate::grid::*;
+use crate::random::Rng;
+use crate::timing;
+use crate::visual;
 use std::io::Read;
 
 pub struct Day17 {}
@@ -11,151 +15,197 @@ impl Day for Day17 {
         "17"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input(), 2022));
+    fn params(&self) -> Params {
+        Params::new()
+            .with("part1.rocks", "2022")
+            .with("part2.rocks", "1000000000000")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input(), 1000000000000));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("rocks")
+            .and_then(|n| self.part1_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("rocks")
+            .and_then(|n| self.part2_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
 const WIDTH: usize = 7;
 
-const ROCKS: &[&[[u8; WIDTH]]] = &[
-    &[[b'.', b'.', b'#', b'#', b'#', b'#', b'.']],
-    &[
-        [b'.', b'.', b'.', b'#', b'.', b'.', b'.'],
-        [b'.', b'.', b'#', b'#', b'#', b'.', b'.'],
-        [b'.', b'.', b'.', b'#', b'.', b'.', b'.'],
-    ],
-    &[
-        [b'.', b'.', b'.', b'.', b'#', b'.', b'.'],
-        [b'.', b'.', b'.', b'.', b'#', b'.', b'.'],
-        [b'.', b'.', b'#', b'#', b'#', b'.', b'.'],
-    ],
-    &[
-        [b'.', b'.', b'#', b'.', b'.', b'.', b'.'],
-        [b'.', b'.', b'#', b'.', b'.', b'.', b'.'],
-        [b'.', b'.', b'#', b'.', b'.', b'.', b'.'],
-        [b'.', b'.', b'#', b'.', b'.', b'.', b'.'],
-    ],
-    &[
-        [b'.', b'.', b'#', b'#', b'.', b'.', b'.'],
-        [b'.', b'.', b'#', b'#', b'.', b'.', b'.'],
-    ],
+// The number of rows, from the top, shown of the chamber.
+const FRAME_HEIGHT: usize = 40;
+
+const ROCKS: &[&[&str]] = &[
+    &["..####."],
+    &["...#...", "..###..", "...#..."],
+    &["....#..", "....#..", "..###.."],
+    &["..#....", "..#....", "..#....", "..#...."],
+    &["..##...", "..##..."],
 ];
 
-impl Day17 {
-    // fn print(chamber: &Vec<[u8; WIDTH]>) {
-    //     for y in (0..chamber.len()).rev() {
-    //         println!(
-    //             "{}",
-    //             chamber[y]
-    //                 .into_iter()
-    //                 .map(|b| b as char)
-    //                 .collect::<String>()
-    //         );
-    //     }
-    //     println!();
-    // }
-
-    fn process(jets_in: &Vec<bool>, n: usize, compute_cycle: bool) -> BoxResult<Output> {
-        let jets_len = jets_in.len();
-        let mut jets = jets_in.iter().cycle();
-        let mut rocks = ROCKS.iter().cycle();
-        let mut chamber: Vec<[u8; WIDTH]> = Vec::new();
-        let mut jet_count = 0;
-        let mut seen = HashMap::<(usize, usize, Vec<[u8; 7]>), usize>::new();
-        for nr in 0..n {
-            let k = (
-                nr % ROCKS.len(),
-                jet_count % jets_len,
-                chamber
-                    .iter()
-                    .rev()
-                    .take(10)
-                    .map(|x| x.to_owned())
-                    .collect::<Vec<_>>(),
-            );
-            if compute_cycle {
-                if let Some(&prefix_len) = seen.get(&k) {
-                    let period = nr - prefix_len;
-                    let cycles = (n - prefix_len) / period;
-                    let suffix_len = (n - prefix_len) % period;
-                    let h1 = Self::process(jets_in, prefix_len, false).unwrap();
-                    let h2 = chamber.len();
-                    let h3 =
-                        Self::process(jets_in, prefix_len + period + suffix_len, false).unwrap();
-                    return Ok(h1 + cycles * (h2 - h1) + h3 - h2);
+// The chamber grows upwards, so its first row is the bottom one.
+struct Tunnel<'a> {
+    jets: &'a [bool],
+    shapes: Vec<Grid<u8>>,
+    chamber: Grid<u8>,
+    rock: usize,
+    jet: usize,
+}
+
+impl<'a> Tunnel<'a> {
+    fn new(jets: &'a [bool]) -> BoxResult<Self> {
+        let shapes = ROCKS
+            .iter()
+            .map(|rock| Grid::from_lines(rock, None, |c| Some(c as u8)))
+            .collect::<BoxResult<Vec<_>>>()?;
+        Ok(Self {
+            jets,
+            shapes,
+            chamber: Grid::new(WIDTH, 0, b'.'),
+            rock: 0,
+            jet: 0,
+        })
+    }
+
+    // The chamber as seen from the side, top row first.
+    fn render(&self) -> String {
+        self.chamber
+            .rows()
+            .rev()
+            .map(|row| {
+                row.iter()
+                    .map(|&b| b as char)
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    fn height(&self) -> usize {
+        self.chamber.height()
+    }
+
+    // The next rock and jet, and the shape of the top of the chamber, which
+    // together decide how the chamber grows from here on.
+    fn fingerprint(&self) -> (usize, usize, Vec<u8>) {
+        (
+            self.rock,
+            self.jet,
+            self.chamber
+                .rows()
+                .rev()
+                .take(10)
+                .flatten()
+                .copied()
+                .collect(),
+        )
+    }
+
+    fn drop_rock(&mut self) -> BoxResult<()> {
+        let chamber = &mut self.chamber;
+        let mut rock = self
+            .shapes
+            .get(self.rock)
+            .with_reason(Reason::OutOfBounds)?
+            .to_owned();
+        self.rock = (self.rock + 1) % self.shapes.len();
+        let height = rock.height();
+        // Extend the chamber upwards to fit the rock.
+        let mut ry = chamber.height() + 3;
+        for _n in 0..(3 + height) {
+            chamber.push_row(vec![b'.'; WIDTH]);
+        }
+        loop {
+            // Apply jet
+            let left = *self.jets.get(self.jet).with_reason(Reason::OutOfBounds)?;
+            self.jet = (self.jet + 1) % self.jets.len();
+            if (0..height).all(|y| {
+                let rl = rock.row(height - y - 1).unwrap();
+                let cl = chamber.row(ry + y).unwrap();
+                if left {
+                    let x = rl.iter().position(|b| *b == b'#').unwrap(); // XXX
+                    x > 0 && cl[x - 1] != b'#'
                 } else {
-                    seen.insert(k, nr);
+                    let x = rl.iter().rposition(|b| *b == b'#').unwrap(); // XXX
+                    x < WIDTH - 1 && cl[x + 1] != b'#'
                 }
-            }
-            let mut rock = rocks.next().ok_or(AocError)?.to_owned().to_owned();
-            let height = rock.len();
-            // Extend the chamber upwards to fit the rock.
-            let mut ry = chamber.len() + 3;
-            for _n in 0..(3 + height) {
-                chamber.push([b'.', b'.', b'.', b'.', b'.', b'.', b'.']);
-            }
-            loop {
-                // Apply jet
-                let left = *jets.next().ok_or(AocError)?;
-                if (0..height).all(|y| {
-                    let rl = rock[height - y - 1];
-                    let cl = chamber[ry + y];
+            }) {
+                for y in 0..height {
+                    let l = rock.row_mut(y).unwrap();
                     if left {
-                        let x = rl.iter().position(|b| *b == b'#').unwrap(); // XXX
-                        x > 0 && cl[x - 1] != b'#'
+                        l.rotate_left(1);
                     } else {
-                        let x = rl.iter().rposition(|b| *b == b'#').unwrap(); // XXX
-                        x < WIDTH - 1 && cl[x + 1] != b'#'
-                    }
-                }) {
-                    for l in rock.iter_mut() {
-                        if left {
-                            l.rotate_left(1);
-                        } else {
-                            l.rotate_right(1);
-                        }
+                        l.rotate_right(1);
                     }
                 }
-                jet_count += 1;
-                // If the rock hs reached the floor let it rest.
-                if ry == 0 {
-                    break;
-                }
-                // Also, if it hits another rock, let it rest.
-                if (0..height).any(|y| {
-                    let rl = rock[height - y - 1];
-                    let cl = chamber[ry - 1 + y];
-                    rl.iter()
-                        .zip(cl.iter())
-                        .any(|(a, b)| *a == b'#' && *b == b'#')
-                }) {
-                    break;
-                }
-                // Drop the rock one step.
-                ry -= 1;
             }
-            // Let rock rest in chamber.
-            for y in 0..height {
-                let rl = rock[height - y - 1];
-                let cl = &mut chamber[ry + y];
-                for (c, r) in cl.iter_mut().zip(rl.iter()) {
-                    if *r == b'#' {
-                        *c = b'#'
-                    }
-                }
+            // If the rock hs reached the floor let it rest.
+            if ry == 0 {
+                break;
             }
-            // Remove empty top of chamber
-            while chamber.ends_with(&[[b'.', b'.', b'.', b'.', b'.', b'.', b'.']]) {
-                chamber.pop();
+            // Also, if it hits another rock, let it rest.
+            if (0..height).any(|y| {
+                let rl = rock.row(height - y - 1).unwrap();
+                let cl = chamber.row(ry - 1 + y).unwrap();
+                rl.iter()
+                    .zip(cl.iter())
+                    .any(|(a, b)| *a == b'#' && *b == b'#')
+            }) {
+                break;
             }
-            //Self::print(&chamber);
+            // Drop the rock one step.
+            ry -= 1;
         }
-        Ok(chamber.len())
+        // Let rock rest in chamber.
+        for y in 0..height {
+            let rl = rock.row(height - y - 1).unwrap();
+            let cl = chamber.row_mut(ry + y).unwrap();
+            for (c, r) in cl.iter_mut().zip(rl.iter()) {
+                if *r == b'#' {
+                    *c = b'#'
+                }
+            }
+        }
+        // Remove empty top of chamber
+        while chamber
+            .rows()
+            .next_back()
+            .is_some_and(|row| row.iter().all(|&b| b == b'.'))
+        {
+            chamber.pop_row();
+        }
+        trace!(3, "{}", self.render());
+        // Only the top of the chamber is shown, the rest is long settled.
+        visual::frame(|| self.render().lines().take(FRAME_HEIGHT).join("\n"));
+        Ok(())
+    }
+}
+
+impl Day17 {
+    // The chamber's height after n rocks, extrapolated once the way the
+    // rocks fall repeats.
+    fn process(jets: &[bool], n: usize) -> BoxResult<Output> {
+        let mut tunnel = Tunnel::new(jets)?;
+        let height = cycle::extrapolate(
+            &mut tunnel,
+            n,
+            Tunnel::drop_rock,
+            Tunnel::fingerprint,
+            |tunnel| tunnel.height() as i64,
+        )?;
+        Ok(height as usize)
     }
 
     fn parse(input: &mut dyn Read) -> BoxResult<Vec<bool>> {
@@ -165,52 +215,39 @@ impl Day17 {
                 let r: BoxResult<_> = r.map_err(|e| e.into());
                 r
             })
-            .filter(|b| b.as_ref().map_or(true, |b| *b != b'\n'))
-            .map(|b| {
+            .enumerate()
+            .filter(|(_, b)| b.as_ref().map_or(true, |b| *b != b'\n'))
+            .map(|(i, b)| {
                 b.and_then(|b| match b {
                     b'<' => Ok(true),
                     b'>' => Ok(false),
-                    _ => Err(AocError)?,
+                    _ => Err(AocError::new(Reason::Parse)
+                        .column(i + 1)
+                        .token(&(b as char)))?,
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
+        if jets.is_empty() {
+            Err(AocError::new(Reason::MissingField))?
+        }
         Ok(jets)
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        let jets = Self::parse(input)?;
-        Self::process(&jets, n, true)
+        let jets = timing::parsing(|| Self::parse(input))?;
+        Self::process(&jets, n)
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        let jets = Self::parse(input)?;
-        Self::process(&jets, n, true)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, n: usize, f: Output) {
-        assert_eq!(Day17 {}.part1_impl(&mut s.as_bytes(), n).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>", 2022, 3068);
-    }
-
-    fn test2(s: &str, n: usize, f: Output) {
-        assert_eq!(Day17 {}.part2_impl(&mut s.as_bytes(), n).ok(), Some(f));
+        let jets = timing::parsing(|| Self::parse(input))?;
+        Self::process(&jets, n)
     }
 
-    #[test]
-    fn part2() {
-        test2(
-            ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>",
-            1000000000000,
-            1514285714288,
-        );
+    /// A pattern of `size` jets.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| if rng.chance(0.5) { '<' } else { '>' })
+            .collect::<String>()
+            + "\n"
     }
 }
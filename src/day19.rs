@@ -1,4 +1,8 @@
+use crate::cancel;
 use crate::day::*;
+use crate::pattern::Pattern;
+use crate::random::Rng;
+use crate::timing;
 use std::cmp::{max, min};
 use std::collections::HashSet;
 use std::iter;
@@ -13,12 +17,60 @@ impl Day for Day19 {
         "19"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn params(&self) -> Params {
+        Params::new()
+            .with("part1.minutes", "24")
+            .with("part2.minutes", "32")
+            .with("part2.blueprints", "3")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| self.part1_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| Ok((n, params.get("blueprints")?)))
+            .and_then(|(n, count)| self.part2_impl(&mut *input(), n, count))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
+    }
+
+    fn has_reference(&self, _part: usize) -> bool {
+        true
+    }
+
+    fn reference_params(&self) -> Params {
+        Params::new().with("minutes", "16")
+    }
+
+    fn reference(
+        &self,
+        part: usize,
+        input: &dyn Fn() -> Box<dyn io::Read>,
+        params: &Params,
+    ) -> Answer {
+        params
+            .get("minutes")
+            .and_then(|n| {
+                let count = if part == 1 {
+                    usize::MAX
+                } else {
+                    params.get("blueprints")?
+                };
+                Self::reference_impl(&mut *input(), part, n, count)
+            })
+            .in_day(self.tag())
+            .into()
     }
 }
 
@@ -53,71 +105,140 @@ struct Blueprint {
     geode: GeodeCost,
 }
 
+const BLUEPRINT: Pattern = Pattern::new(
+    "Blueprint {int}: \
+     Each ore robot costs {int} ore. \
+     Each clay robot costs {int} ore. \
+     Each obsidian robot costs {int} ore and {int} clay. \
+     Each geode robot costs {int} ore and {int} obsidian.",
+);
+
 impl FromStr for Blueprint {
     type Err = Box<dyn error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.trim().replace(':', "");
-        let mut tokens = s.split_whitespace();
-        let id = tokens.nth(1).ok_or(AocError)?.parse::<usize>()?;
-        let ore_ore = tokens.nth(4).ok_or(AocError)?.parse::<usize>()?;
-        let clay_ore = tokens.nth(5).ok_or(AocError)?.parse::<usize>()?;
-        let obsidian_ore = tokens.nth(5).ok_or(AocError)?.parse::<usize>()?;
-        let obsidian_clay = tokens.nth(2).ok_or(AocError)?.parse::<usize>()?;
-        let geode_ore = tokens.nth(5).ok_or(AocError)?.parse::<usize>()?;
-        let geode_obsidian = tokens.nth(2).ok_or(AocError)?.parse::<usize>()?;
-        if tokens.count() != 1 {
-            Err(AocError)?
-        } else {
-            let blueprint = Blueprint {
-                id,
-                ore: OreCost { ore: ore_ore },
-                clay: ClayCost { ore: clay_ore },
-                obsidian: ObsidianCost {
-                    ore: obsidian_ore,
-                    clay: obsidian_clay,
-                },
-                geode: GeodeCost {
-                    ore: geode_ore,
-                    obsidian: geode_obsidian,
-                },
-            };
-            //println!("{:?}", blueprint);
-            Ok(blueprint)
-        }
+        let mut fields = BLUEPRINT.fields(s.trim())?;
+        Ok(Blueprint {
+            id: fields.field()?,
+            ore: OreCost {
+                ore: fields.field()?,
+            },
+            clay: ClayCost {
+                ore: fields.field()?,
+            },
+            obsidian: ObsidianCost {
+                ore: fields.field()?,
+                clay: fields.field()?,
+            },
+            geode: GeodeCost {
+                ore: fields.field()?,
+                obsidian: fields.field()?,
+            },
+        })
     }
 }
 
 impl Blueprint {
     fn process(&self, n: usize, do_quality: bool) -> BoxResult<usize> {
-        let inventories = (0..n).fold(
+        let inventories = (0..n).take_while(|_| !cancel::cancelled()).fold(
             iter::once(Inventory {
                 ore_robot: 1,
                 ..Default::default()
             })
             .collect::<HashSet<_>>(),
-            |inventories, _t| {
-                // println!("{}, {:?}", _t, inventories.len());
+            |inventories, t| {
+                let left = n - t - 1;
                 let inventories: HashSet<_> = inventories
                     .into_iter()
                     .flat_map(|inventory| inventory.spend(self).into_iter())
+                    .map(|inventory| inventory.cap(self, left))
+                    .collect();
+                // Drop the inventories that cannot crack as many geodes as
+                // another is sure to.
+                let floor = inventories
+                    .iter()
+                    .map(|inventory| inventory.geode + inventory.geode_robot * left)
+                    .max()
+                    .unwrap_or(0);
+                let inventories: HashSet<_> = inventories
+                    .into_iter()
+                    .filter(|inventory| inventory.geode_bound(self, left) >= floor)
                     .collect();
-                // println!(
-                //     "{:?}",
-                //     inventories.iter().map(|inventory| inventory.geode).max()
-                // );
+                trace!(
+                    2,
+                    "blueprint {} minute {} inventories {} geodes {:?}",
+                    self.id,
+                    t + 1,
+                    inventories.len(),
+                    inventories.iter().map(|inventory| inventory.geode).max()
+                );
                 inventories
             },
         );
+        cancel::check()?;
         let rv = if do_quality { self.id } else { 1 }
             * inventories
                 .iter()
                 .map(|Inventory { geode, .. }| geode)
                 .max()
-                .ok_or(AocError)?;
-        // println!("{}", rv);
+                .with_reason(Reason::NoSolution)?;
+        trace!(1, "blueprint {} gives {}", self.id, rv);
         Ok(rv)
     }
+
+    /// The most ore any robot costs.
+    fn most_ore(&self) -> usize {
+        max(
+            max(self.ore.ore, self.clay.ore),
+            max(self.obsidian.ore, self.geode.ore),
+        )
+    }
+
+    /// The most geodes cracked in `n` minutes, trying every order of building
+    /// robots, or stopping, with each robot built as soon as it can be
+    /// afforded, which never leaves less of anything than building it later.
+    fn most_geodes(&self, n: usize) -> usize {
+        fn after(blueprint: &Blueprint, inventory: Inventory, n: usize) -> usize {
+            let mut most = inventory.geode + inventory.geode_robot * n;
+            for robot in 0..4 {
+                let (ore, clay, obsidian) = match robot {
+                    0 => (blueprint.ore.ore, 0, 0),
+                    1 => (blueprint.clay.ore, 0, 0),
+                    2 => (blueprint.obsidian.ore, blueprint.obsidian.clay, 0),
+                    _ => (blueprint.geode.ore, 0, blueprint.geode.obsidian),
+                };
+                let mut inventory = inventory;
+                for t in 0..n {
+                    let affordable = inventory.ore >= ore
+                        && inventory.clay >= clay
+                        && inventory.obsidian >= obsidian;
+                    inventory.collect();
+                    if affordable {
+                        inventory.ore -= ore;
+                        inventory.clay -= clay;
+                        inventory.obsidian -= obsidian;
+                        match robot {
+                            0 => inventory.ore_robot += 1,
+                            1 => inventory.clay_robot += 1,
+                            2 => inventory.obsidian_robot += 1,
+                            _ => inventory.geode_robot += 1,
+                        }
+                        most = max(most, after(blueprint, inventory, n - t - 1));
+                        break;
+                    }
+                }
+            }
+            most
+        }
+        after(
+            self,
+            Inventory {
+                ore_robot: 1,
+                ..Default::default()
+            },
+            n,
+        )
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -134,90 +255,92 @@ struct Inventory {
 
 impl Inventory {
     fn spend(self, blueprint: &Blueprint) -> HashSet<Inventory> {
-        //println!("spend {:?}", self);
+        trace!(3, "spend {:?}", self);
         let mut inventories = HashSet::new();
         let mut temp = self;
         temp.collect();
-        let geode_robots_creatable = min(
-            self.obsidian / blueprint.geode.obsidian,
-            self.ore / blueprint.geode.ore,
-        );
-        let obsidian_robots_creatable = min(
-            self.clay / blueprint.obsidian.clay,
-            self.ore / blueprint.obsidian.ore,
-        );
-        let clay_robots_creatable = self.ore / blueprint.clay.ore;
-        let ore_robots_creatable = self.ore / blueprint.ore.ore;
-        if geode_robots_creatable >= 1 {
+        let can_build = |ore: usize, clay: usize, obsidian: usize| {
+            self.ore >= ore && self.clay >= clay && self.obsidian >= obsidian
+        };
+        let mut build = |ore: usize, clay: usize, obsidian: usize, robot: fn(&mut Self)| {
             let mut new = temp;
-            new.obsidian -= blueprint.geode.obsidian;
-            new.ore -= blueprint.geode.ore;
-            new.geode_robot += 1;
+            new.ore -= ore;
+            new.clay -= clay;
+            new.obsidian -= obsidian;
+            robot(&mut new);
             inventories.insert(new);
+        };
+        // Only one robot is built a minute, so there is no use for more
+        // robots of a kind than any robot costs of what they collect.
+        let geode = can_build(blueprint.geode.ore, 0, blueprint.geode.obsidian);
+        if geode {
+            build(blueprint.geode.ore, 0, blueprint.geode.obsidian, |i| {
+                i.geode_robot += 1
+            });
         }
-        // Heuristic: Only allow collecting obsidian, when we can build at most
-        // one geode robot, but not if we already have reached the needed
-        // capacity per turn.
-        if obsidian_robots_creatable >= 1
-            && geode_robots_creatable < 2
-            && self.obsidian_robot < blueprint.geode.obsidian
-        {
-            let mut new = temp;
-            new.clay -= blueprint.obsidian.clay;
-            new.ore -= blueprint.obsidian.ore;
-            new.obsidian_robot += 1;
-            inventories.insert(new);
+        let obsidian = can_build(blueprint.obsidian.ore, blueprint.obsidian.clay, 0);
+        if obsidian && self.obsidian_robot < blueprint.geode.obsidian {
+            build(blueprint.obsidian.ore, blueprint.obsidian.clay, 0, |i| {
+                i.obsidian_robot += 1
+            });
         }
-        // Heuristic: Only allow collecting clay, when we can build at most one
-        // obsidian robot, but not if we already have reached the needed
-        // capacity per turn.
-        if clay_robots_creatable >= 1
-            && obsidian_robots_creatable < 2
-            && self.clay_robot < blueprint.obsidian.clay
-        {
-            let mut new = temp;
-            new.ore -= blueprint.clay.ore;
-            new.clay_robot += 1;
-            inventories.insert(new);
+        let clay = can_build(blueprint.clay.ore, 0, 0);
+        if clay && self.clay_robot < blueprint.obsidian.clay {
+            build(blueprint.clay.ore, 0, 0, |i| i.clay_robot += 1);
         }
-        // Heuristic: Only allow collecting ore, if we have not already reached
-        // the needed capacity to build something otherwise buildable per turn.
-        if ore_robots_creatable >= 1
-            && self.ore_robot
-                < max(
-                    max(blueprint.ore.ore, blueprint.clay.ore),
-                    max(
-                        if self.clay >= blueprint.obsidian.clay {
-                            blueprint.obsidian.ore
-                        } else {
-                            0
-                        },
-                        if self.obsidian >= blueprint.geode.obsidian {
-                            blueprint.geode.ore
-                        } else {
-                            0
-                        },
-                    ),
-                )
-        {
-            let mut new = temp;
-            new.ore -= blueprint.ore.ore;
-            new.ore_robot += 1;
-            inventories.insert(new);
+        let ore = can_build(blueprint.ore.ore, 0, 0);
+        if ore && self.ore_robot < blueprint.most_ore() {
+            build(blueprint.ore.ore, 0, 0, |i| i.ore_robot += 1);
         }
-        // Heuristic: Only allow waiting when we must, or only have one other
-        // option.
-        if geode_robots_creatable
-            + obsidian_robots_creatable
-            + clay_robots_creatable
-            + ore_robots_creatable
-            < 2
-        {
+        // Saving up is pointless when every robot can be built right away,
+        // as building one later leaves no more of anything.
+        if !(geode && obsidian && clay && ore) {
             inventories.insert(temp);
         }
         inventories
     }
 
+    /// The inventory with anything beyond what can still be spent in the
+    /// `left` minutes thrown away, which changes nothing but makes more
+    /// inventories the same.
+    fn cap(mut self, blueprint: &Blueprint, left: usize) -> Self {
+        let most_ore = blueprint.most_ore();
+        let cap = |stock: &mut usize, robots: usize, most: usize| {
+            *stock = min(
+                *stock,
+                (most * left).saturating_sub(robots * left.saturating_sub(1)),
+            );
+        };
+        cap(&mut self.ore, self.ore_robot, most_ore);
+        cap(&mut self.clay, self.clay_robot, blueprint.obsidian.clay);
+        cap(
+            &mut self.obsidian,
+            self.obsidian_robot,
+            blueprint.geode.obsidian,
+        );
+        self
+    }
+
+    /// No fewer geodes than can be cracked in the `left` minutes: as many as
+    /// if ore were free and a robot of each kind could be built every minute.
+    fn geode_bound(mut self, blueprint: &Blueprint, left: usize) -> usize {
+        for _ in 0..left {
+            let geode = self.obsidian >= blueprint.geode.obsidian;
+            let obsidian = self.clay >= blueprint.obsidian.clay;
+            self.collect();
+            if geode {
+                self.obsidian -= blueprint.geode.obsidian;
+                self.geode_robot += 1;
+            }
+            if obsidian {
+                self.clay -= blueprint.obsidian.clay;
+                self.obsidian_robot += 1;
+            }
+            self.clay_robot += 1;
+        }
+        self.geode
+    }
+
     fn collect(&mut self) {
         self.ore += self.ore_robot;
         self.clay += self.clay_robot;
@@ -227,53 +350,96 @@ impl Inventory {
 }
 
 impl Day19 {
-    fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
-            .map(|l| l.map_err(|e| e.into()))
-            .map(|l: BoxResult<_>| {
-                l.and_then(|l| l.as_str().parse::<Blueprint>()?.process(24, true))
-            })
-            .sum()
-    }
-
-    fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
-            .take(3)
-            .map(|l| l.map_err(|e| e.into()))
-            .map(|l: BoxResult<_>| {
-                l.and_then(|l| l.as_str().parse::<Blueprint>()?.process(32, false))
-            })
-            .product()
+    fn blueprints(input: &mut dyn io::Read, count: usize) -> BoxResult<Vec<Blueprint>> {
+        timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .take(count)
+                .enumerate()
+                .map(|(i, l)| l?.parse::<Blueprint>().at_line(i + 1))
+                .collect()
+        })
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day19 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
+    fn part1_impl(&self, input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
+        Self::blueprints(input, usize::MAX)?
+            .iter()
+            .map(|blueprint| blueprint.process(n, true))
+            .sum()
     }
 
-    #[test]
-    fn part1() {
-        test1(
-            "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
-Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.",
-            33,
-        );
+    fn part2_impl(&self, input: &mut dyn io::Read, n: usize, count: usize) -> BoxResult<Output> {
+        Self::blueprints(input, count)?
+            .iter()
+            .map(|blueprint| blueprint.process(n, false))
+            .product()
     }
 
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day19 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
+    /// The sum of the quality levels, or the product of the geodes of the
+    /// first `count` blueprints, found the slow way.  Every robot costs
+    /// something in the puzzle, so none may be free.
+    fn reference_impl(
+        input: &mut dyn io::Read,
+        part: usize,
+        n: usize,
+        count: usize,
+    ) -> BoxResult<Output> {
+        let blueprints = io::BufReader::new(input)
+            .lines()
+            .take(count)
+            .enumerate()
+            .map(|(i, l)| {
+                l.map_err(|e| e.into())
+                    .and_then(|l| l.parse::<Blueprint>())
+                    .and_then(|blueprint| {
+                        let costs = [
+                            blueprint.ore.ore,
+                            blueprint.clay.ore,
+                            blueprint.obsidian.ore,
+                            blueprint.obsidian.clay,
+                            blueprint.geode.ore,
+                            blueprint.geode.obsidian,
+                        ];
+                        if costs.contains(&0) {
+                            Err(AocError::new(Reason::Parse).token("0"))?
+                        }
+                        Ok(blueprint)
+                    })
+                    .at_line(i + 1)
+            })
+            .collect::<BoxResult<Vec<_>>>()?;
+        Ok(if part == 1 {
+            blueprints
+                .iter()
+                .map(|blueprint| blueprint.id * blueprint.most_geodes(n))
+                .sum()
+        } else {
+            blueprints
+                .iter()
+                .map(|blueprint| blueprint.most_geodes(n))
+                .product()
+        })
     }
 
-    #[test]
-    fn part2() {
-        test2("Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
-Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.",
-              56 * 62);
+    /// `size` blueprints.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (1..=size.max(1))
+            .map(|i| {
+                format!(
+                    "Blueprint {}: \
+                     Each ore robot costs {} ore. \
+                     Each clay robot costs {} ore. \
+                     Each obsidian robot costs {} ore and {} clay. \
+                     Each geode robot costs {} ore and {} obsidian.\n",
+                    i,
+                    rng.range(2..=4),
+                    rng.range(2..=4),
+                    rng.range(2..=4),
+                    rng.range(5..=20),
+                    rng.range(2..=4),
+                    rng.range(5..=20)
+                )
+            })
+            .collect()
     }
 }
@@ -1,4 +1,7 @@
+use crate::cancel;
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 
 pub struct Day20 {}
 
@@ -9,12 +12,16 @@ impl Day for Day20 {
         "20"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -23,17 +30,22 @@ impl Day20 {
     where
         F: Fn(&mut Vec<(usize, Output)>, usize, &Vec<(usize, Output)>) -> Result<(), AocError>,
     {
-        let mut v = io::BufReader::new(input)
-            .lines()
-            .map(|l| l.map_err(|e| e.into()))
-            .map(|l: BoxResult<_>| l.and_then(|l| Ok(l.as_str().parse::<i64>()?)))
-            .enumerate()
-            .map(|(i, n)| n.map(|n| (i, n * f)))
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut v = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .map(|l| l.map_err(|e| e.into()))
+                .map(|l: BoxResult<_>| l.and_then(|l| Ok(l.as_str().parse::<i64>()?)))
+                .enumerate()
+                .map(|(i, n)| n.map(|n| (i, n * f)))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
         let l = v.len();
         let init = v.clone();
         mix(&mut v, l, &init)?;
-        let o = v.iter().position(|&(_, x)| x == 0).ok_or(AocError)?;
+        let o = v
+            .iter()
+            .position(|&(_, x)| x == 0)
+            .with_reason(Reason::NoSolution)?;
         Ok(v[(o + 1000) % l].1 + v[(o + 2000) % l].1 + v[(o + 3000) % l].1)
     }
 
@@ -54,7 +66,13 @@ impl Day20 {
         init: &Vec<(usize, Output)>,
     ) -> Result<(), AocError> {
         for (i, n) in init {
-            let j = v.iter().position(|&(j, _)| *i == j).ok_or(AocError)?;
+            if cancel::cancelled() {
+                return Err(AocError::new(Reason::TimedOut));
+            }
+            let j = v
+                .iter()
+                .position(|&(j, _)| *i == j)
+                .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
             v.remove(j);
             let k = (j as Output + n).rem_euclid((l - 1) as Output) as usize;
             v.insert(k, init[*i]);
@@ -77,45 +95,25 @@ impl Day20 {
             },
         )
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day20 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "1
-2
--3
-3
--2
-0
-4",
-            3,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day20 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
 
-    #[test]
-    fn part2() {
-        test2(
-            "1
-2
--3
-3
--2
-0
-4",
-            1623178306,
-        );
+    /// `size` numbers, at least two, exactly one of them 0.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let count = size.max(2);
+        let zero = rng.range(0..=count - 1);
+        (0..count)
+            .map(|i| {
+                let n = if i == zero {
+                    0
+                } else {
+                    let n = rng.range(1..=10000);
+                    if rng.chance(0.5) {
+                        -n
+                    } else {
+                        n
+                    }
+                };
+                format!("{}\n", n)
+            })
+            .collect()
     }
 }
@@ -1,5 +1,8 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::str::FromStr;
 
 pub struct Day21 {}
@@ -11,12 +14,16 @@ impl Day for Day21 {
         "21"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
@@ -37,7 +44,7 @@ impl FromStr for Operation {
             "-" => Self::Minus,
             "*" => Self::Times,
             "/" => Self::Divide,
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::Parse).token(s))?,
         })
     }
 }
@@ -55,12 +62,12 @@ impl FromStr for Expression {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut tokens = s.split_whitespace();
         let expression = Self {
-            left: tokens.next().ok_or(AocError)?.to_owned(),
-            operation: tokens.next().ok_or(AocError)?.parse()?,
-            right: tokens.next().ok_or(AocError)?.to_owned(),
+            left: tokens.next().with_reason(Reason::MissingField)?.to_owned(),
+            operation: tokens.next().with_reason(Reason::MissingField)?.parse()?,
+            right: tokens.next().with_reason(Reason::MissingField)?.to_owned(),
         };
         if tokens.next().is_some() {
-            Err(AocError.into())
+            Err(AocError::new(Reason::Parse).token(s).into())
         } else {
             Ok(expression)
         }
@@ -90,11 +97,17 @@ impl FromStr for Job {
     type Err = Box<dyn error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(if s.chars().next().ok_or(AocError)?.is_ascii_digit() {
-            Self::Number(s.parse()?)
-        } else {
-            Self::Expression(s.parse()?)
-        })
+        Ok(
+            if s.chars()
+                .next()
+                .with_reason(Reason::MissingField)?
+                .is_ascii_digit()
+            {
+                Self::Number(s.parse()?)
+            } else {
+                Self::Expression(s.parse()?)
+            },
+        )
     }
 }
 
@@ -108,7 +121,10 @@ impl FromStr for Monkey {
     type Err = Box<dyn error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (name, job) = s.split_once(':').ok_or(AocError)?;
+        let (name, job) = s
+            .split_once(':')
+            .with_reason(Reason::MissingField)
+            .with_token(s)?;
         Ok(Self {
             name: name.to_owned(),
             job: job.trim().parse::<Job>()?,
@@ -131,8 +147,16 @@ impl Monkey {
         Ok(match &self.job {
             Job::Number(_) => None,
             Job::Expression(expression) => {
-                let left = choir.monkeys.get(&expression.left).ok_or(AocError)?;
-                let right = choir.monkeys.get(&expression.right).ok_or(AocError)?;
+                let left = choir
+                    .monkeys
+                    .get(&expression.left)
+                    .with_reason(Reason::MissingField)
+                    .with_token(&expression.left)?;
+                let right = choir
+                    .monkeys
+                    .get(&expression.right)
+                    .with_reason(Reason::MissingField)
+                    .with_token(&expression.right)?;
                 if let Some(mut path) = left.find_humn(choir)? {
                     path.push(true);
                     Some(path)
@@ -148,9 +172,17 @@ impl Monkey {
 
     fn deduce_humn_yell(&self, choir: &Choir, mut path: Vec<bool>, n: Output) -> BoxResult<Output> {
         Ok(if let Job::Expression(expression) = &self.job {
-            let left = choir.monkeys.get(&expression.left).ok_or(AocError)?;
-            let right = choir.monkeys.get(&expression.right).ok_or(AocError)?;
-            let humn_is_left = path.pop().ok_or(AocError)?;
+            let left = choir
+                .monkeys
+                .get(&expression.left)
+                .with_reason(Reason::MissingField)
+                .with_token(&expression.left)?;
+            let right = choir
+                .monkeys
+                .get(&expression.right)
+                .with_reason(Reason::MissingField)
+                .with_token(&expression.right)?;
+            let humn_is_left = path.pop().with_reason(Reason::OutOfBounds)?;
             let (humn_branch, other) = if humn_is_left {
                 (left, right)
             } else {
@@ -185,7 +217,7 @@ impl Monkey {
         } else if self.name == "humn" {
             n
         } else {
-            Err(AocError)?
+            Err(AocError::new(Reason::NoSolution))?
         })
     }
 }
@@ -200,9 +232,10 @@ impl Choir {
         io::BufReader::new(input)
             .lines()
             .map(|l| l.map_err(|e| e.into()))
-            .map(|l: BoxResult<_>| {
+            .enumerate()
+            .map(|(i, l): (_, BoxResult<_>)| {
                 l.and_then(|l| {
-                    let monkey = l.as_str().parse::<Monkey>()?;
+                    let monkey = l.as_str().parse::<Monkey>().at_line(i + 1)?;
                     Ok((monkey.name.to_owned(), monkey))
                 })
             })
@@ -211,81 +244,189 @@ impl Choir {
     }
 
     fn yell(&self, name: &str) -> BoxResult<Output> {
-        self.monkeys.get(name).ok_or(AocError)?.yell(self)
+        self.monkeys
+            .get(name)
+            .with_reason(Reason::MissingField)
+            .with_token(name)?
+            .yell(self)
+    }
+}
+
+// Numbers above this are not made larger, so that none overflows.
+const LARGE: Output = 1_000_000;
+
+/// Monkeys to yell given numbers, with names all different.
+struct Troop<'a> {
+    rng: &'a mut Rng,
+    names: HashSet<String>,
+    jobs: Vec<String>,
+}
+
+impl Troop<'_> {
+    fn name(&mut self) -> String {
+        loop {
+            let name = (0..4)
+                .map(|_| self.rng.range(b'a'..=b'z') as char)
+                .collect::<String>();
+            if name != "root" && name != "humn" && self.names.insert(name.to_owned()) {
+                return name;
+            }
+        }
+    }
+
+    /// A monkey yelling `value`, with `count` monkeys under it doing an
+    /// operation, itself included, and the others yelling a number.
+    fn yelling(&mut self, value: Output, count: usize) -> String {
+        let name = self.name();
+        if count == 0 {
+            self.jobs.push(format!("{}: {}", name, value));
+            return name;
+        }
+        let divisors = (2..=9)
+            .filter(|d| value > 0 && value % d == 0)
+            .collect::<Vec<_>>();
+        let (left, operation, right) = match self.rng.range(0..=3) {
+            1 if value <= LARGE => {
+                let right = self.rng.range(0..=100);
+                (value + right, '-', right)
+            }
+            2 if !divisors.is_empty() => {
+                let right = *self.rng.pick(&divisors);
+                (value / right, '*', right)
+            }
+            3 if value <= LARGE => {
+                let right = self.rng.range(2..=5);
+                (value * right, '/', right)
+            }
+            _ => {
+                let left = self.rng.range(0..=value);
+                (left, '+', value - left)
+            }
+        };
+        let left_count = self.rng.range(0..=count - 1);
+        let left = self.yelling(left, left_count);
+        let right = self.yelling(right, count - 1 - left_count);
+        self.jobs
+            .push(format!("{}: {} {} {}", name, left, operation, right));
+        name
     }
 }
 
 impl Day21 {
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let choir = Choir::from(input)?;
+        let choir = timing::parsing(|| Choir::from(input))?;
         choir.yell("root")
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let choir = Choir::from(input)?;
+        let choir = timing::parsing(|| Choir::from(input))?;
         Self::deduce(choir)
     }
 
     fn deduce(choir: Choir) -> BoxResult<Output> {
-        let root = choir.monkeys.get("root").ok_or(AocError)?;
-        let path = root.find_humn(&choir)?.ok_or(AocError)?;
+        let root = choir
+            .monkeys
+            .get("root")
+            .with_reason(Reason::MissingField)
+            .with_token("root")?;
+        let path = root.find_humn(&choir)?.with_reason(Reason::NoSolution)?;
         root.deduce_humn_yell(&choir, path, 0)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day21 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "root: pppw + sjmn
-dbpl: 5
-cczh: sllz + lgvd
-zczc: 2
-ptdq: humn - dvpt
-dvpt: 3
-lfqf: 4
-humn: 5
-ljgn: 2
-sjmn: drzm * dbpl
-sllz: 4
-pppw: cczh / lfqf
-lgvd: ljgn * ptdq
-drzm: hmdt - zczc
-hmdt: 32",
-            152,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day21 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "root: pppw + sjmn
-dbpl: 5
-cczh: sllz + lgvd
-zczc: 2
-ptdq: humn - dvpt
-dvpt: 3
-lfqf: 4
-humn: 5
-ljgn: 2
-sjmn: drzm * dbpl
-sllz: 4
-pppw: cczh / lfqf
-lgvd: ljgn * ptdq
-drzm: hmdt - zczc
-hmdt: 32",
-            301,
-        );
+    /// The jobs of `size` monkeys doing an operation, root among them, and of
+    /// those yelling the numbers they operate on.  Each monkey is given the
+    /// number it must yell, humn the one that makes root's operands equal,
+    /// and its job is made up to yell that exactly.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let count = size.saturating_sub(1);
+        let depth = rng.range(0..=count.min(16));
+        // The other monkeys are shared among the operands off the way from
+        // root to humn.
+        let mut shares = vec![0; depth + 1];
+        for _ in depth..count {
+            shares[rng.range(0..=depth)] += 1;
+        }
+        let mut troop = Troop {
+            rng,
+            names: HashSet::new(),
+            jobs: vec![],
+        };
+        let target = troop.rng.range(1..=10000);
+        let other = troop.yelling(target, shares[0]);
+        let mut name = if depth == 0 {
+            "humn".to_owned()
+        } else {
+            troop.name()
+        };
+        troop.jobs.push(if troop.rng.chance(0.5) {
+            format!("root: {} + {}", name, other)
+        } else {
+            format!("root: {} + {}", other, name)
+        });
+        let mut wanted = target;
+        let mut path = vec![];
+        for (i, &share) in shares.iter().enumerate().skip(1) {
+            let child = if i == depth {
+                "humn".to_owned()
+            } else {
+                troop.name()
+            };
+            let divisors = (2..=9)
+                .filter(|d| wanted > 0 && wanted % d == 0)
+                .collect::<Vec<_>>();
+            let (value, operation, operand, humn_is_left) = match troop.rng.range(0..=4) {
+                1 if wanted <= LARGE => {
+                    let operand = troop.rng.range(0..=100);
+                    (wanted + operand, '-', operand, true)
+                }
+                2 if wanted <= LARGE => {
+                    let value = troop.rng.range(0..=100);
+                    (value, '-', wanted + value, false)
+                }
+                3 if !divisors.is_empty() => {
+                    let operand = *troop.rng.pick(&divisors);
+                    (wanted / operand, '*', operand, troop.rng.chance(0.5))
+                }
+                4 if wanted <= LARGE => {
+                    let operand = troop.rng.range(2..=5);
+                    (wanted * operand, '/', operand, true)
+                }
+                _ => {
+                    let operand = troop.rng.range(0..=wanted);
+                    (wanted - operand, '+', operand, troop.rng.chance(0.5))
+                }
+            };
+            let other = troop.yelling(operand, share);
+            troop.jobs.push(if humn_is_left {
+                format!("{}: {} {} {}", name, child, operation, other)
+            } else {
+                format!("{}: {} {} {}", name, other, operation, child)
+            });
+            path.push((operation, humn_is_left, operand));
+            (name, wanted) = (child, value);
+        }
+        // What humn yells in the first part is left to chance, unless that
+        // overflows on the way up to root.
+        let yell = |humn: Output| {
+            path.iter()
+                .rev()
+                .try_fold(humn, |n, &(operation, humn_is_left, operand)| {
+                    match (operation, humn_is_left) {
+                        ('+', _) => n.checked_add(operand),
+                        ('-', true) => n.checked_sub(operand),
+                        ('-', false) => operand.checked_sub(n),
+                        ('*', _) => n.checked_mul(operand),
+                        _ => n.checked_div(operand),
+                    }
+                })
+                .and_then(|n| n.checked_add(target))
+        };
+        let humn = troop.rng.range(0..=10000);
+        troop.jobs.push(format!(
+            "humn: {}",
+            if yell(humn).is_some() { humn } else { wanted }
+        ));
+        troop.rng.shuffle(&mut troop.jobs);
+        troop.jobs.iter().map(|job| format!("{}\n", job)).collect()
     }
 }
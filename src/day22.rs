@@ -1,4 +1,8 @@
 use crate::day::*;
+use crate::grid::Grid;
+use crate::random::Rng;
+use crate::timing;
+use crate::visual;
 use std::cmp::max;
 
 pub struct Day22 {}
@@ -10,47 +14,80 @@ impl Day for Day22 {
         "22"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input(), Board::flat_step));
+    fn params(&self) -> Params {
+        Params::new().with("part2.layout", "input")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input(), Board::cube_step));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input(), Board::flat_step)
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        // The cube is folded differently in the example and in the inputs.
+        params
+            .get::<String>("layout")
+            .and_then(|layout| match layout.as_str() {
+                "input" => self.part2_impl(&mut *input(), Board::cube_step),
+                "example" => self.part2_impl(&mut *input(), Board::cube_step_example),
+                _ => Err(AocError::new(Reason::Parse).token(&layout))?,
+            })
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, params: &Params) -> BoxResult<String> {
+        params
+            .for_part(2)
+            .get::<String>("layout")
+            .and_then(|layout| Self::generate_impl(rng, size, &layout))
     }
 }
 
 type Pos = (usize, usize, usize);
 
+// A walk can be a single step, so a frame is shown every so many moves.
+const MOVES_PER_FRAME: usize = 10;
+
 #[derive(Debug)]
 struct Board {
-    map: Vec<Vec<Option<u8>>>,
+    map: Grid<Option<u8>>,
     horizontal: Vec<(usize, usize)>,
     vertical: Vec<(usize, usize)>,
     size: usize,
 }
 
 impl Board {
-    fn from(map: Vec<Vec<Option<u8>>>) -> BoxResult<Self> {
+    fn from(map: Grid<Option<u8>>) -> BoxResult<Self> {
         let horizontal = map
-            .iter()
+            .rows()
             .map(|l| {
-                let left = l.iter().position(|t| t.is_some()).ok_or(AocError)?;
-                let right = l.iter().rposition(|t| t.is_some()).ok_or(AocError)?;
+                let left = l
+                    .iter()
+                    .position(|t| t.is_some())
+                    .with_reason(Reason::Parse)?;
+                let right = l
+                    .iter()
+                    .rposition(|t| t.is_some())
+                    .with_reason(Reason::Parse)?;
                 Ok((left, right + 1))
             })
             .collect::<BoxResult<Vec<_>>>()?;
-        let vertical = (0..map.first().ok_or(AocError)?.len())
+        let vertical = (0..map.width())
             .map(|i| {
-                let column = map
-                    .iter()
-                    .map(|l| l.get(i).ok_or(AocError))
-                    .collect::<Result<Vec<_>, _>>()?;
-                let top = column.iter().position(|t| t.is_some()).ok_or(AocError)?;
-                let bottom = column.iter().rposition(|t| t.is_some()).ok_or(AocError)?;
+                let top = map
+                    .column(i)
+                    .position(|t| t.is_some())
+                    .with_reason(Reason::Parse)?;
+                let bottom = map
+                    .column(i)
+                    .rposition(|t| t.is_some())
+                    .with_reason(Reason::Parse)?;
                 Ok((top, bottom + 1))
             })
             .collect::<BoxResult<Vec<_>>>()?;
-        let size = max(map.len(), map.get(0).ok_or(AocError)?.len()) / 4;
+        let size = max(map.height(), map.width()) / 4;
         Ok(Self {
             map,
             vertical,
@@ -59,8 +96,9 @@ impl Board {
         })
     }
 
-    fn starting_position(&self) -> Result<Pos, AocError> {
-        Ok((0, self.horizontal.first().ok_or(AocError)?.0, 0))
+    fn starting_position(&self) -> BoxResult<Pos> {
+        let (column, _) = self.horizontal.first().with_reason(Reason::Parse)?;
+        Ok((0, *column, 0))
     }
 
     fn walk(
@@ -68,17 +106,17 @@ impl Board {
         pos: Pos,
         distance: usize,
         step: fn(&Self, Pos) -> Result<Pos, AocError>,
+        trail: &mut Option<Grid<char>>,
     ) -> Result<Pos, AocError> {
         let mut pos = pos;
         for _ in 0..distance {
+            Self::mark(trail, pos);
             let new_pos = step(self, pos)?;
             if self
                 .map
-                .get(new_pos.0)
-                .ok_or(AocError)?
-                .get(new_pos.1)
-                .ok_or(AocError)?
-                .ok_or(AocError)?
+                .get((new_pos.0, new_pos.1))
+                .ok_or_else(|| AocError::new(Reason::OutOfBounds))?
+                .ok_or_else(|| AocError::new(Reason::OutOfBounds))?
                 == b'#'
             {
                 break;
@@ -90,9 +128,15 @@ impl Board {
 
     fn flat_step(&self, pos: Pos) -> Result<Pos, AocError> {
         let (row, column, facing) = pos;
-        let row_limits = self.vertical.get(column).ok_or(AocError)?;
+        let row_limits = self
+            .vertical
+            .get(column)
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
         let row_width = row_limits.1 - row_limits.0;
-        let column_limits = self.horizontal.get(row).ok_or(AocError)?;
+        let column_limits = self
+            .horizontal
+            .get(row)
+            .ok_or_else(|| AocError::new(Reason::OutOfBounds))?;
         let column_width = column_limits.1 - column_limits.0;
         Ok(match facing {
             0 => (
@@ -115,7 +159,7 @@ impl Board {
                 column,
                 facing,
             ),
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::OutOfBounds))?,
         })
     }
 
@@ -151,7 +195,7 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
             2 => match pos.2 {
                 0 => {
@@ -182,7 +226,7 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
             3 => match pos.2 {
                 0 => {
@@ -213,7 +257,7 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
             4 => match pos.2 {
                 0 => {
@@ -244,7 +288,7 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
             5 => match pos.2 {
                 0 => {
@@ -275,7 +319,7 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
             6 => match pos.2 {
                 0 => {
@@ -306,207 +350,222 @@ impl Board {
                         (pos.0 - 1, pos.1, pos.2)
                     }
                 }
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
             },
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::OutOfBounds))?,
         })
     }
 
-    // fn cube_step_test(
-    //     &self,
-    //     pos: Pos,
-    // ) -> Result<Pos, AocError> {
-    //     let (face, face_pos) = self.to_face_test(pos)?;
-    //     Ok(match face {
-    //         1 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(6, (self.size - 1 - face_pos.0, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(4, (0, face_pos.1, 1))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(3, (0, face_pos.0, 1))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(2, (0, self.size - 1 - face_pos.1, 1))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         2 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(3, (face_pos.0, 0, 0))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(5, (face_pos.0, self.size - 1 - face_pos.1, 3))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(6, (self.size - 1, self.size - 1 - face_pos.0, 3))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(1, (0, self.size - 1 - face_pos.1, 1))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         3 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(4, (face_pos.0, 0, 0))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(5, (self.size - 1 - face_pos.1, 0, 0))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(2, (face_pos.0, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(1, (face_pos.1, 0, 0))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         4 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(6, (0, self.size - 1 - face_pos.0, 1))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(5, (0, face_pos.1, 1))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(3, (face_pos.0, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(1, (self.size - 1, face_pos.1, 3))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         5 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(6, (face_pos.0, 0, 0))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(2, (self.size - 1, self.size - 1 - face_pos.1, 3))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(3, (self.size - 1, self.size - 1 - face_pos.1, 3))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(4, (self.size - 1, face_pos.1, 3))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         6 => match pos.2 {
-    //             0 => {
-    //                 if face_pos.1 + 1 == self.size {
-    //                     self.from_face_test(1, (self.size - 1 - face_pos.0, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0, pos.1 + 1, pos.2)
-    //                 }
-    //             }
-    //             1 => {
-    //                 if face_pos.0 + 1 == self.size {
-    //                     self.from_face_test(2, (self.size - 1 - face_pos.1, 0, 0))?
-    //                 } else {
-    //                     (pos.0 + 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             2 => {
-    //                 if face_pos.1 == 0 {
-    //                     self.from_face_test(5, (face_pos.0, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0, pos.1 - 1, pos.2)
-    //                 }
-    //             }
-    //             3 => {
-    //                 if face_pos.0 == 0 {
-    //                     self.from_face_test(4, (self.size - 1 - face_pos.1, self.size - 1, 2))?
-    //                 } else {
-    //                     (pos.0 - 1, pos.1, pos.2)
-    //                 }
-    //             }
-    //             _ => Err(AocError)?,
-    //         },
-    //         _ => Err(AocError)?,
-    //     })
-    // }
+    fn cube_step_example(&self, pos: Pos) -> Result<Pos, AocError> {
+        let (face, face_pos) = self.cvt_to_face_example(pos)?;
+        Ok(match face {
+            1 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(
+                            6,
+                            (self.size - 1 - face_pos.0, self.size - 1, 2),
+                        )?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(4, (0, face_pos.1, 1))?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(3, (0, face_pos.0, 1))?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(2, (0, self.size - 1 - face_pos.1, 1))?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            2 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(3, (face_pos.0, 0, 0))?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(5, (face_pos.0, self.size - 1 - face_pos.1, 3))?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(
+                            6,
+                            (self.size - 1, self.size - 1 - face_pos.0, 3),
+                        )?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(1, (0, self.size - 1 - face_pos.1, 1))?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            3 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(4, (face_pos.0, 0, 0))?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(5, (self.size - 1 - face_pos.1, 0, 0))?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(2, (face_pos.0, self.size - 1, 2))?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(1, (face_pos.1, 0, 0))?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            4 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(6, (0, self.size - 1 - face_pos.0, 1))?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(5, (0, face_pos.1, 1))?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(3, (face_pos.0, self.size - 1, 2))?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(1, (self.size - 1, face_pos.1, 3))?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            5 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(6, (face_pos.0, 0, 0))?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(
+                            2,
+                            (self.size - 1, self.size - 1 - face_pos.1, 3),
+                        )?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(
+                            3,
+                            (self.size - 1, self.size - 1 - face_pos.1, 3),
+                        )?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(4, (self.size - 1, face_pos.1, 3))?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            6 => match pos.2 {
+                0 => {
+                    if face_pos.1 + 1 == self.size {
+                        self.cvt_from_face_example(
+                            1,
+                            (self.size - 1 - face_pos.0, self.size - 1, 2),
+                        )?
+                    } else {
+                        (pos.0, pos.1 + 1, pos.2)
+                    }
+                }
+                1 => {
+                    if face_pos.0 + 1 == self.size {
+                        self.cvt_from_face_example(2, (self.size - 1 - face_pos.1, 0, 0))?
+                    } else {
+                        (pos.0 + 1, pos.1, pos.2)
+                    }
+                }
+                2 => {
+                    if face_pos.1 == 0 {
+                        self.cvt_from_face_example(5, (face_pos.0, self.size - 1, 2))?
+                    } else {
+                        (pos.0, pos.1 - 1, pos.2)
+                    }
+                }
+                3 => {
+                    if face_pos.0 == 0 {
+                        self.cvt_from_face_example(
+                            4,
+                            (self.size - 1 - face_pos.1, self.size - 1, 2),
+                        )?
+                    } else {
+                        (pos.0 - 1, pos.1, pos.2)
+                    }
+                }
+                _ => Err(AocError::new(Reason::OutOfBounds))?,
+            },
+            _ => Err(AocError::new(Reason::OutOfBounds))?,
+        })
+    }
 
     fn travel(
         &self,
@@ -514,47 +573,56 @@ impl Board {
         moves: &[Move],
         step: fn(&Self, Pos) -> Result<Pos, AocError>,
     ) -> Result<Pos, AocError> {
-        moves.iter().fold(Ok(pos), |pos, m| {
-            let pos = pos?;
-            Ok(match m {
-                Move::Walk(distance) => self.walk(pos, *distance, step)?,
+        // The map with the way walked so far, kept only when it is shown.
+        let mut trail = visual::enabled().then(|| self.map.map(|t| t.map_or(' ', char::from)));
+        moves.iter().enumerate().try_fold(pos, |pos, (i, m)| {
+            let pos = match m {
+                Move::Walk(distance) => self.walk(pos, *distance, step, &mut trail)?,
                 Move::TurnLeft => (pos.0, pos.1, (pos.2 + 3).rem_euclid(4)),
                 Move::TurnRight => (pos.0, pos.1, (pos.2 + 1).rem_euclid(4)),
-            })
+            };
+            if let Some(trail) = &mut trail {
+                if i % MOVES_PER_FRAME == 0 || i + 1 == moves.len() {
+                    let mut frame = trail.to_owned();
+                    frame[(pos.0, pos.1)] = 'E';
+                    visual::frame(|| frame.to_string());
+                }
+            }
+            Ok(pos)
         })
     }
 
-    // fn to_face_test(
-    //     &self,
-    //     pos: Pos,
-    // ) -> Result<(usize, Pos), AocError> {
-    //     let new_pos = (pos.0 % self.size, pos.1 % self.size, pos.2);
-    //     Ok(match (pos.0 / self.size, pos.1 / self.size) {
-    //         (0, 2) => (1, new_pos),
-    //         (1, 0) => (2, new_pos),
-    //         (1, 1) => (3, new_pos),
-    //         (1, 2) => (4, new_pos),
-    //         (2, 2) => (5, new_pos),
-    //         (2, 3) => (6, new_pos),
-    //         (_, _) => Err(AocError)?,
-    //     })
-    // }
-    //
-    // fn from_face_test(
-    //     &self,
-    //     face: usize,
-    //     pos: Pos,
-    // ) -> Result<Pos, AocError> {
-    //     Ok(match face {
-    //         1 => (pos.0, self.size * 2 + pos.1, pos.2),
-    //         2 => (self.size + pos.0, pos.1, pos.2),
-    //         3 => (self.size + pos.0, self.size + pos.1, pos.2),
-    //         4 => (self.size + pos.0, self.size * 2 + pos.1, pos.2),
-    //         5 => (self.size * 2 + pos.0, self.size * 2 + pos.1, pos.2),
-    //         6 => (self.size * 2 + pos.0, self.size * 3 + pos.1, pos.2),
-    //         _ => Err(AocError)?,
-    //     })
-    // }
+    // Mark a position on the trail with the way it was left.
+    fn mark(trail: &mut Option<Grid<char>>, pos: Pos) {
+        if let Some(trail) = trail {
+            trail[(pos.0, pos.1)] = ['>', 'v', '<', '^'][pos.2];
+        }
+    }
+
+    fn cvt_to_face_example(&self, pos: Pos) -> Result<(usize, Pos), AocError> {
+        let new_pos = (pos.0 % self.size, pos.1 % self.size, pos.2);
+        Ok(match (pos.0 / self.size, pos.1 / self.size) {
+            (0, 2) => (1, new_pos),
+            (1, 0) => (2, new_pos),
+            (1, 1) => (3, new_pos),
+            (1, 2) => (4, new_pos),
+            (2, 2) => (5, new_pos),
+            (2, 3) => (6, new_pos),
+            (_, _) => Err(AocError::new(Reason::OutOfBounds))?,
+        })
+    }
+
+    fn cvt_from_face_example(&self, face: usize, pos: Pos) -> Result<Pos, AocError> {
+        Ok(match face {
+            1 => (pos.0, self.size * 2 + pos.1, pos.2),
+            2 => (self.size + pos.0, pos.1, pos.2),
+            3 => (self.size + pos.0, self.size + pos.1, pos.2),
+            4 => (self.size + pos.0, self.size * 2 + pos.1, pos.2),
+            5 => (self.size * 2 + pos.0, self.size * 2 + pos.1, pos.2),
+            6 => (self.size * 2 + pos.0, self.size * 3 + pos.1, pos.2),
+            _ => Err(AocError::new(Reason::OutOfBounds))?,
+        })
+    }
 
     fn cvt_to_face(&self, pos: Pos) -> Result<(usize, Pos), AocError> {
         let new_pos = (pos.0 % self.size, pos.1 % self.size, pos.2);
@@ -565,7 +633,7 @@ impl Board {
             (2, 0) => (4, new_pos),
             (2, 1) => (5, new_pos),
             (3, 0) => (6, new_pos),
-            (_, _) => Err(AocError)?,
+            (_, _) => Err(AocError::new(Reason::OutOfBounds))?,
         })
     }
 
@@ -577,7 +645,7 @@ impl Board {
             4 => (self.size * 2 + pos.0, pos.1, pos.2),
             5 => (self.size * 2 + pos.0, self.size + pos.1, pos.2),
             6 => (self.size * 3 + pos.0, pos.1, pos.2),
-            _ => Err(AocError)?,
+            _ => Err(AocError::new(Reason::OutOfBounds))?,
         })
     }
 }
@@ -591,35 +659,23 @@ enum Move {
 
 impl Day22 {
     fn parse(input: &mut dyn io::Read) -> BoxResult<(Board, Vec<Move>)> {
-        let mut bytes = io::BufReader::new(input)
-            .split(b'\n')
-            .map(|l| l.map_err(|e| e.into()))
-            .collect::<BoxResult<Vec<_>>>()?;
-        let (moves, map) = bytes.split_last_mut().ok_or(AocError)?;
-        let (blank_line, map) = map.split_last_mut().ok_or(AocError)?;
+        let lines = io::BufReader::new(input)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?;
+        let (moves, map) = lines.split_last().with_reason(Reason::MissingField)?;
+        let (blank_line, map) = map.split_last().with_reason(Reason::MissingField)?;
         if !blank_line.is_empty() {
-            Err(AocError)?
+            Err(AocError::new(Reason::Parse).token(blank_line))?
         }
-        let len = map.iter().map(|row| row.len()).max().ok_or(AocError)?;
-        let map = map
-            .iter_mut()
-            .map(|l| {
-                l.resize(len, b' ');
-                l.iter()
-                    .map(|b| {
-                        Ok(match b {
-                            b' ' => None,
-                            b'#' | b'.' => Some(*b),
-                            _ => Err(AocError)?,
-                        })
-                    })
-                    .collect::<BoxResult<Vec<_>>>()
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let map = Grid::from_lines(map, Some(' '), |c| match c {
+            ' ' => Some(None),
+            '#' | '.' => Some(Some(c as u8)),
+            _ => None,
+        })?;
         let board = Board::from(map)?;
-        let moves = moves.iter().fold(Ok(Vec::new()), |r: BoxResult<_>, b| {
+        let moves = moves.bytes().fold(Ok(Vec::new()), |r: BoxResult<_>, b| {
             let mut moves = r?;
-            match *b {
+            match b {
                 b'0'..=b'9' => {
                     let n = (b - b'0') as usize;
                     let last = moves.pop();
@@ -635,7 +691,7 @@ impl Day22 {
                 }
                 b'L' => moves.push(Move::TurnLeft),
                 b'R' => moves.push(Move::TurnRight),
-                _ => Err(AocError)?,
+                _ => Err(AocError::new(Reason::Parse).token(&(b as char)))?,
             }
             Ok(moves)
         })?;
@@ -647,7 +703,7 @@ impl Day22 {
         input: &mut dyn io::Read,
         step: fn(&Board, Pos) -> Result<Pos, AocError>,
     ) -> BoxResult<Output> {
-        let (board, moves) = Self::parse(input)?;
+        let (board, moves) = timing::parsing(|| Self::parse(input))?;
         let pos = board.travel(board.starting_position()?, &moves, step)?;
         Ok((pos.0 + 1) * 1000 + (pos.1 + 1) * 4 + pos.2)
     }
@@ -657,73 +713,52 @@ impl Day22 {
         input: &mut dyn io::Read,
         step: fn(&Board, Pos) -> Result<Pos, AocError>,
     ) -> BoxResult<Output> {
-        let (board, moves) = Self::parse(input)?;
+        let (board, moves) = timing::parsing(|| Self::parse(input))?;
         let pos = board.travel(board.starting_position()?, &moves, step)?;
         Ok((pos.0 + 1) * 1000 + (pos.1 + 1) * 4 + pos.2)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn test1(s: &str, f: Output) {
-        assert_eq!(
-            Day22 {}
-                .part1_impl(&mut s.as_bytes(), Board::flat_step)
-                .ok(),
-            Some(f)
-        );
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "        ...#
-        .#..
-        #...
-        ....
-...#.......#
-........#...
-..#....#....
-..........#.
-        ...#....
-        .....#..
-        .#......
-        ......#.
-
-10R5L5R10L4R5L5",
-            6032,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(
-            Day22 {}
-                .part2_impl(&mut s.as_bytes(), Board::cube_step_test)
-                .ok(),
-            Some(f)
-        );
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "        ...#
-        .#..
-        #...
-        ....
-...#.......#
-........#...
-..#....#....
-..........#.
-        ...#....
-        .....#..
-        .#......
-        ......#.
-
-10R5L5R10L4R5L5",
-            5031,
-        );
+    /// A map of faces of `size` tiles square, at least 2, laid out to fold
+    /// as in the inputs or the example, and `size` moves along it.
+    fn generate_impl(rng: &mut Rng, size: usize, layout: &str) -> BoxResult<String> {
+        let faces: [(usize, usize); 6] = match layout {
+            "input" => [(0, 1), (0, 2), (1, 1), (2, 0), (2, 1), (3, 0)],
+            "example" => [(0, 2), (1, 0), (1, 1), (1, 2), (2, 2), (2, 3)],
+            _ => Err(AocError::new(Reason::Parse).token(layout))?,
+        };
+        let side = size.max(2);
+        let rows = (faces.iter().map(|&(row, _)| row).max().unwrap_or(0) + 1) * side;
+        let mut text = String::new();
+        for row in 0..rows {
+            let columns = faces
+                .iter()
+                .filter(|&&(face_row, _)| face_row == row / side)
+                .map(|&(_, column)| column + 1)
+                .max()
+                .unwrap_or(0)
+                * side;
+            for column in 0..columns {
+                text.push(if !faces.contains(&(row / side, column / side)) {
+                    ' '
+                } else if rng.chance(0.1) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        // The walk starts on the first tile of the map, which must be open.
+        let start = text.find('#').filter(|&i| i < text.find('.').unwrap_or(0));
+        if let Some(start) = start {
+            text.replace_range(start..=start, ".");
+        }
+        text.push('\n');
+        text.push_str(&rng.range(1..=50).to_string());
+        for _ in 1..size {
+            text.push(*rng.pick(&['L', 'R']));
+            text.push_str(&rng.range(1..=50).to_string());
+        }
+        Ok(text + "\n")
     }
 }
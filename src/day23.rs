@@ -1,4 +1,10 @@
+use crate::cancel;
 use crate::day::*;
+use crate::geometry::*;
+use crate::grid::Grid;
+use crate::random::Rng;
+use crate::timing;
+use crate::visual;
 use itertools::Itertools;
 use std::collections::HashSet;
 
@@ -11,17 +17,31 @@ impl Day for Day23 {
         "23"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input(), 10));
+    fn params(&self) -> Params {
+        Params::new().with("part1.rounds", "10")
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, params: &Params) -> Answer {
+        params
+            .get("rounds")
+            .and_then(|n| self.part1_impl(&mut *input(), n))
+            .in_day(self.tag())
+            .into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct Coord(i64, i64);
+type Coord = Point2<i64>;
+
+// The directions the elves consider, in the order of the first round.
+const ORDER: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
 
 #[derive(Debug)]
 struct Board {
@@ -33,38 +53,45 @@ impl Board {
         self.elves.len()
     }
 
-    fn area(&self) -> Result<usize, AocError> {
-        let min_x = self.elves.iter().min_by_key(|elf| elf.0).ok_or(AocError)?.0;
-        let max_x = self.elves.iter().max_by_key(|elf| elf.0).ok_or(AocError)?.0;
-        let min_y = self.elves.iter().min_by_key(|elf| elf.1).ok_or(AocError)?.1;
-        let max_y = self.elves.iter().max_by_key(|elf| elf.1).ok_or(AocError)?.1;
+    fn area(&self) -> BoxResult<usize> {
+        let (min_x, max_x) = self
+            .elves
+            .iter()
+            .map(|elf| elf.x)
+            .minmax()
+            .into_option()
+            .with_reason(Reason::NoSolution)?;
+        let (min_y, max_y) = self
+            .elves
+            .iter()
+            .map(|elf| elf.y)
+            .minmax()
+            .into_option()
+            .with_reason(Reason::NoSolution)?;
         Ok(((max_x - min_x + 1) * (max_y - min_y + 1)) as usize)
     }
 
+    // Whether any of the three positions towards the given direction is
+    // occupied.
+    fn is_occupied(&self, elf: Coord, dir: Dir) -> bool {
+        let side = dir.turn_right().step();
+        (-1..=1).any(|i| self.elves.contains(&(elf + dir + side * i)))
+    }
+
     fn propositions(&self, step: usize) -> Vec<(Coord, Coord)> {
         self.elves
             .iter()
-            .flat_map(|elf| {
-                let (north_occupied, south_occupied, west_occupied, east_occupied) = (
-                    (-1..=1).any(|i| self.elves.contains(&Coord(elf.0 + i, elf.1 - 1))),
-                    (-1..=1).any(|i| self.elves.contains(&Coord(elf.0 + i, elf.1 + 1))),
-                    (-1..=1).any(|i| self.elves.contains(&Coord(elf.0 - 1, elf.1 + i))),
-                    (-1..=1).any(|i| self.elves.contains(&Coord(elf.0 + 1, elf.1 + i))),
-                );
-                if !north_occupied && !south_occupied && !west_occupied && !east_occupied {
+            .flat_map(|&elf| {
+                let occupied = ORDER.map(|dir| self.is_occupied(elf, dir));
+                if !occupied.contains(&true) {
                     None
                 } else {
                     (0..4)
-                        .flat_map(|i| match (step + i) % 4 {
-                            0 if !north_occupied => Some(Coord(elf.0, elf.1 - 1)),
-                            1 if !south_occupied => Some(Coord(elf.0, elf.1 + 1)),
-                            2 if !west_occupied => Some(Coord(elf.0 - 1, elf.1)),
-                            3 if !east_occupied => Some(Coord(elf.0 + 1, elf.1)),
-                            _ => None,
-                        })
-                        .next()
+                        .map(|i| (step + i) % 4)
+                        .find(|&i| !occupied[i])
+                        .map(|i| elf + ORDER[i])
                 }
-                .map(|target| (*elf, target))
+                .map(|target| (elf, target))
             })
             .collect()
     }
@@ -74,35 +101,59 @@ impl Board {
         self.elves.insert(order.1);
         Ok(())
     }
+
+    // The smallest rectangle holding the elves, drawn as in the puzzle.
+    fn render(&self) -> String {
+        let (left, right) = self
+            .elves
+            .iter()
+            .map(|elf| elf.x)
+            .minmax()
+            .into_option()
+            .unwrap_or((0, 0));
+        let (top, bottom) = self
+            .elves
+            .iter()
+            .map(|elf| elf.y)
+            .minmax()
+            .into_option()
+            .unwrap_or((0, 0));
+        (top..=bottom)
+            .map(|y| {
+                (left..=right)
+                    .map(|x| {
+                        if self.elves.contains(&Coord::new(x, y)) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect()
+    }
 }
 impl Day23 {
     fn parse(input: &mut dyn io::Read) -> BoxResult<Board> {
-        let elves: HashSet<Coord> = HashSet::new();
-        let elves = io::BufReader::new(input)
-            .split(b'\n')
-            .enumerate()
-            .map(|(i, l)| l.map(|l| (i, l)).map_err(|e| e.into()))
-            .fold(Ok(elves), |elves: BoxResult<_>, r: BoxResult<_>| {
-                let elves = elves?;
-                let (row, l) = r?;
-                l.iter().enumerate().fold(Ok(elves), |elves, (column, b)| {
-                    let mut elves = elves?;
-                    match b {
-                        b'#' => {
-                            elves.insert(Coord(column as i64, row as i64));
-                        }
-                        b'.' => {}
-                        _ => Err(AocError)?,
-                    }
-                    Ok(elves)
-                })
-            })?;
+        let grove = Grid::parse(input, |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })?;
+        let elves = grove
+            .iter()
+            .filter(|(_, &elf)| elf)
+            .map(|((row, column), _)| Coord::new(column as i64, row as i64))
+            .collect();
         Ok(Board { elves })
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read, n: usize) -> BoxResult<Output> {
-        let mut board = Self::parse(input)?;
+        let mut board = timing::parsing(|| Self::parse(input))?;
+        visual::frame(|| board.render());
         for i in 0..n {
+            cancel::check()?;
             let propositions = board.propositions(i);
             let duplicates = propositions
                 .iter()
@@ -115,13 +166,16 @@ impl Day23 {
             {
                 board.execute(proposition)?;
             }
+            visual::frame(|| board.render());
         }
         Ok(board.area()? - board.elf_count())
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let mut board = Self::parse(input)?;
+        let mut board = timing::parsing(|| Self::parse(input))?;
+        visual::frame(|| board.render());
         for i in 0.. {
+            cancel::check()?;
             let propositions = board.propositions(i);
             let duplicates = propositions
                 .iter()
@@ -138,48 +192,17 @@ impl Day23 {
             for proposition in propositions {
                 board.execute(proposition)?;
             }
+            visual::frame(|| board.render());
         }
-        Err(AocError)?
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day23 {}.part1_impl(&mut s.as_bytes(), 10).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "....#..
-..###.#
-#...#.#
-.#...##
-#.###..
-##.#.##
-.#..#..",
-            110,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day23 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
+        Err(AocError::new(Reason::NoSolution))?
     }
 
-    #[test]
-    fn part2() {
-        test2(
-            "....#..
-..###.#
-#...#.#
-.#...##
-#.###..
-##.#.##
-.#..#..",
-            20,
-        );
+    /// A `size` by `size` grove, half of it elves.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let side = size.max(1);
+        let mut grove =
+            Grid::new(side, side, b'.').map(|_| if rng.chance(0.5) { b'#' } else { b'.' });
+        grove[(rng.range(0..=side - 1), rng.range(0..=side - 1))] = b'#';
+        grove.to_string()
     }
 }
@@ -1,8 +1,11 @@
 use crate::day::*;
+use crate::geometry::*;
+use crate::grid::Grid;
+use crate::random::Rng;
+use crate::search;
+use crate::timing;
+use crate::visual;
 use std::collections::HashSet;
-use std::hash::Hash;
-use std::iter;
-use std::ops::ControlFlow::{Break, Continue};
 
 pub struct Day24 {}
 
@@ -13,348 +16,204 @@ impl Day for Day24 {
         "24"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
     }
 
-    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part2_impl(&mut *input()));
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
     }
-}
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-struct Coord {
-    row: usize,
-    column: usize,
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
+    }
 }
 
-impl Coord {
-    fn neighbours(&self, board: &Board) -> HashSet<Coord> {
-        [
-            if self.row > 0 {
-                Coord {
-                    row: self.row - 1,
-                    column: self.column,
-                }
-            } else {
-                // XXX
-                Coord {
-                    row: self.row + 1,
-                    column: self.column,
-                }
-            },
-            Coord {
-                row: self.row + 1,
-                column: self.column,
-            },
-            Coord {
-                row: self.row,
-                column: self.column - 1,
-            },
-            Coord {
-                row: self.row,
-                column: self.column + 1,
-            },
-        ]
+type Coord = Point2<usize>;
+
+// The expedition may move within the valley, or stay at the entrance or the
+// exit, which are in the walls.
+fn neighbours(coord: Coord, board: &Board) -> HashSet<Coord> {
+    Dir::ALL
         .into_iter()
-        .filter(|&Coord { row, column }| {
-            (row > 0 && row <= board.height && column > 0 && column <= board.width)
-                || (row == 0 && column == 1)
-                || (row == board.height + 1 && column == board.width)
+        .filter_map(|dir| coord.checked_step(dir))
+        .filter(|&c| {
+            (c.y > 0 && c.y <= board.height && c.x > 0 && c.x <= board.width)
+                || c == board.entrance()
+                || c == board.exit()
         })
         .collect()
-    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-enum Blizzard {
-    LeftHorizontal(Coord),
-    RightHorizontal(Coord),
-    UpVertical(Coord),
-    DownVertical(Coord),
+struct Blizzard {
+    coord: Coord,
+    dir: Dir,
 }
 
-impl Blizzard {
-    fn next(&self, board: &Board) -> Self {
-        match *self {
-            Blizzard::LeftHorizontal(Coord { row, column }) => Blizzard::LeftHorizontal(Coord {
-                row,
-                column: (column + board.width - 2) % board.width + 1,
-            }),
-            Blizzard::RightHorizontal(Coord { row, column }) => Blizzard::RightHorizontal(Coord {
-                row,
-                column: column % board.width + 1,
-            }),
-            Blizzard::UpVertical(Coord { row, column }) => Blizzard::UpVertical(Coord {
-                row: (row + board.height - 2) % board.height + 1,
-                column,
-            }),
-            Blizzard::DownVertical(Coord { row, column }) => Blizzard::DownVertical(Coord {
-                row: row % board.height + 1,
-                column,
-            }),
-        }
-    }
-
-    fn coord(&self) -> Coord {
-        match self {
-            Blizzard::LeftHorizontal(c) => *c,
-            Blizzard::RightHorizontal(c) => *c,
-            Blizzard::UpVertical(c) => *c,
-            Blizzard::DownVertical(c) => *c,
-        }
-    }
-}
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 struct Board {
     width: usize,
     height: usize,
-    blizzards: Vec<Blizzard>,
-    expedition: Coord,
+    blizzards: HashSet<Blizzard>,
 }
 
 impl Board {
-    fn next(&self) -> Vec<Board> {
-        let mut neighbours = self.expedition.neighbours(self);
-        neighbours.insert(self.expedition);
-        //println!("neighbours {:?}", neighbours);
-        let blizzards = self
-            .blizzards
-            .iter()
-            .map(|blizzard| blizzard.next(self))
-            .collect::<Vec<_>>();
-        //println!("blizzards {:?}", blizzards);
-        neighbours
-            .into_iter()
-            .filter(|expedition| {
-                !blizzards
-                    .iter()
-                    .any(|blizzard| blizzard.coord() == *expedition)
-            })
-            .map(|expedition| Board {
-                blizzards: blizzards.to_owned(),
-                expedition,
-                ..*self
-            })
-            .collect::<Vec<_>>()
+    fn entrance(&self) -> Coord {
+        Coord::new(1, 0)
     }
 
-    fn is_at_the_entrance(&self) -> bool {
-        self.expedition == Coord { row: 0, column: 1 }
+    fn exit(&self) -> Coord {
+        Coord::new(self.width, self.height + 1)
     }
 
-    fn is_at_the_gates(&self) -> bool {
-        self.expedition
-            == Coord {
-                row: self.height + 1,
-                column: self.width,
-            }
+    // The blizzards are back where they started after this many minutes.
+    fn period(&self) -> usize {
+        num::integer::lcm(self.width, self.height)
     }
 
-    //     fn print(&self) {
-    //         println!("#.{}", "#".repeat(self.width));
-    //         for row in 1..=self.height {
-    //             print!("#");
-    //             for column in 1..=self.width {
-    //                 let coord = Coord { row, column };
-    //                 let blizzards = self
-    //                     .blizzards
-    //                     .iter()
-    //                     .filter(|blizzard| blizzard.coord() == coord)
-    //                     .collect::<Vec<_>>();
-    //                 print!(
-    //                     "{}",
-    //                     match blizzards.len() {
-    //                         0 =>
-    //                             if self.expedition == coord {
-    //                                 'E'
-    //                             } else {
-    //                                 '.'
-    //                             },
-    //                         1 => match blizzards[0] {
-    //                             Blizzard::LeftHorizontal(_) => '<',
-    //                             Blizzard::RightHorizontal(_) => '>',
-    //                             Blizzard::UpVertical(_) => '^',
-    //                             Blizzard::DownVertical(_) => 'v',
-    //                         },
-    //                         n => (n as u8 + b'0') as char,
-    //                     }
-    //                 );
-    //             }
-    //             println!("#");
-    //         }
-    //         println!("{}.#", "#".repeat(self.width));
-    //     }
+    // Whether no blizzard covers c after t minutes, i.e. none started t
+    // steps back, against its direction, wrapping around inside the walls.
+    fn is_free(&self, c: Coord, t: usize) -> bool {
+        if c == self.entrance() || c == self.exit() {
+            return true;
+        }
+        let (width, height) = (self.width, self.height);
+        let (x, y) = (c.x - 1, c.y - 1);
+        [
+            (
+                Dir::Right,
+                Coord::new((x + width - t % width) % width + 1, c.y),
+            ),
+            (Dir::Left, Coord::new((x + t) % width + 1, c.y)),
+            (
+                Dir::Down,
+                Coord::new(c.x, (y + height - t % height) % height + 1),
+            ),
+            (Dir::Up, Coord::new(c.x, (y + t) % height + 1)),
+        ]
+        .into_iter()
+        .all(|(dir, coord)| !self.blizzards.contains(&Blizzard { coord, dir }))
+    }
+
+    // The time of arrival at to, leaving from at time t.  As the blizzards
+    // repeat, so do the states of the search, which are kept modulo the
+    // period.
+    fn cross(&self, from: Coord, to: Coord, t: usize) -> BoxResult<usize> {
+        let period = self.period();
+        search::bfs(
+            (from, t % period),
+            |&(expedition, t)| {
+                let mut moves = neighbours(expedition, self);
+                moves.insert(expedition);
+                moves
+                    .into_iter()
+                    .filter(move |&c| self.is_free(c, t + 1))
+                    .map(move |c| (c, (t + 1) % period))
+            },
+            |&(expedition, _)| expedition == to,
+        )
+        .map(|path| {
+            for (i, &(expedition, time)) in path.states.iter().enumerate() {
+                trace!(3, "minute {}\n{}", t + i, self.render(expedition, time));
+                visual::frame(|| self.render(expedition, time));
+            }
+            trace!(1, "from {} to {} at minute {}", from, to, t + path.cost);
+            t + path.cost
+        })
+        .with_reason(Reason::NoSolution)
+    }
+
+    // The valley at time t, with the expedition at E and blizzards as *.
+    fn render(&self, expedition: Coord, t: usize) -> String {
+        let mut s = format!("#.{}\n", "#".repeat(self.width));
+        for row in 1..=self.height {
+            s.push('#');
+            for column in 1..=self.width {
+                let coord = Coord::new(column, row);
+                s.push(if coord == expedition {
+                    'E'
+                } else if self.is_free(coord, t) {
+                    '.'
+                } else {
+                    '*'
+                });
+            }
+            s.push_str("#\n");
+        }
+        s + &format!("{}.#\n", "#".repeat(self.width))
+    }
 }
 
 impl Day24 {
     fn parse(input: &mut dyn io::Read) -> BoxResult<Board> {
-        let Ok((blizzards, Some(width), Some(height))) = io::BufReader::new(input)
-            .split(b'\n')
-            .enumerate()
-            .map(|(row, l)| l.map(|l| (row, l)).map_err(|e| e.into()))
-            .fold(Ok((Vec::new(), None, None)), |state: BoxResult<_>, r: BoxResult<_>| {
-                let state = state?;
-                let (row, l) = r?;
-                l.iter().enumerate().fold(Ok(state), |state, (column, b)| {
-                    let (mut blizzards, _, _) = state?;
-                    match b {
-                        b'<' => {
-                            blizzards.push(Blizzard::LeftHorizontal(Coord { row, column }));
-                        }
-                        b'>' => {
-                            blizzards.push(Blizzard::RightHorizontal(Coord { row, column }));
-                        }
-                        b'^' => {
-                            blizzards.push(Blizzard::UpVertical(Coord { row, column }));
-                        }
-                        b'v' => {
-                            blizzards.push(Blizzard::DownVertical(Coord { row, column }));
-                        }
-                        b'#' => {}
-                        b'.' => {}
-                        _ => Err(AocError)?,
-                    }
-                    Ok((blizzards, Some(l.len() - 2), if row > 0 { Some(row - 1) } else { None }))
+        let valley = Grid::parse(input, |c| "<>^v#.".contains(c).then_some(c))?;
+        if valley.width() < 3 || valley.height() < 3 {
+            Err(AocError::new(Reason::Parse))?
+        }
+        let blizzards = valley
+            .iter()
+            .filter_map(|((row, column), c)| {
+                let dir = c.to_string().parse().ok()?;
+                Some(Blizzard {
+                    coord: Coord::new(column, row),
+                    dir,
                 })
-            }) else {
-            Err(AocError)?
-        };
+            })
+            .collect();
         Ok(Board {
-            width,
-            height,
+            width: valley.width() - 2,
+            height: valley.height() - 2,
             blizzards,
-            expedition: Coord { row: 0, column: 1 },
         })
     }
 
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        match (0..).try_fold(
-            (
-                iter::once(Self::parse(input)?).collect::<HashSet<_>>(),
-                HashSet::new(),
-                0usize,
-            ),
-            |(boards, mut state, t), _| {
-                if boards.iter().any(|board| board.is_at_the_gates()) {
-                    return Break(t);
-                }
-                let mut next_boards = HashSet::new();
-                for board in boards {
-                    state.insert(board.to_owned());
-                    for next in board.next() {
-                        next_boards.insert(next);
-                    }
-                }
-                Continue((next_boards, state, t + 1))
-            },
-        ) {
-            Break(t) => Ok(t),
-            _ => Err(AocError.into()),
-        }
+        let board = timing::parsing(|| Self::parse(input))?;
+        board.cross(board.entrance(), board.exit(), 0)
     }
 
     fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        let (t, board) = match (0..).try_fold(
-            (
-                iter::once(Self::parse(input)?).collect::<HashSet<_>>(),
-                HashSet::new(),
-                0usize,
-            ),
-            |(boards, mut state, t), _| {
-                if let Some(board) = boards.iter().find(|board| board.is_at_the_gates()) {
-                    return Break((t, board.to_owned()));
-                }
-                let mut next_boards = HashSet::new();
-                for board in boards {
-                    state.insert(board.to_owned());
-                    for next in board.next() {
-                        next_boards.insert(next);
-                    }
-                }
-                Continue((next_boards, state, t + 1))
-            },
-        ) {
-            Break(t) => Ok::<_, Box<dyn error::Error>>(t),
-            _ => Err(AocError.into()),
-        }?;
-        let (t, board) = match (t..).try_fold(
-            (iter::once(board).collect::<HashSet<_>>(), HashSet::new(), t),
-            |(boards, mut state, t), _| {
-                if let Some(board) = boards.iter().find(|board| board.is_at_the_entrance()) {
-                    return Break((t, board.to_owned()));
+        let board = timing::parsing(|| Self::parse(input))?;
+        let t = board.cross(board.entrance(), board.exit(), 0)?;
+        let t = board.cross(board.exit(), board.entrance(), t)?;
+        board.cross(board.entrance(), board.exit(), t)
+    }
+
+    /// A valley `size` rows high and four times as wide, with blizzards on
+    /// a third of it, that the expedition can cross there, back and there
+    /// again.  None blows up or down in the columns of the entrance and the
+    /// exit, as in the inputs.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        let (height, width) = (size.max(1), 4 * size.max(1));
+        loop {
+            let mut text = format!("#.{}\n", "#".repeat(width));
+            for _ in 0..height {
+                text.push('#');
+                for column in 1..=width {
+                    let dirs: &[char] = if column == 1 || column == width {
+                        &['<', '>']
+                    } else {
+                        &['<', '>', '^', 'v']
+                    };
+                    text.push(if rng.chance(0.3) {
+                        *rng.pick(dirs)
+                    } else {
+                        '.'
+                    });
                 }
-                let mut next_boards = HashSet::new();
-                for board in boards {
-                    state.insert(board.to_owned());
-                    for next in board.next() {
-                        next_boards.insert(next);
-                    }
-                }
-                Continue((next_boards, state, t + 1))
-            },
-        ) {
-            Break(t) => Ok::<_, Box<dyn error::Error>>(t),
-            _ => Err(AocError.into()),
-        }?;
-        match (t..).try_fold(
-            (iter::once(board).collect::<HashSet<_>>(), HashSet::new(), t),
-            |(boards, mut state, t), _| {
-                if let Some(board) = boards.iter().find(|board| board.is_at_the_gates()) {
-                    return Break((t, board.to_owned()));
-                }
-                let mut next_boards = HashSet::new();
-                for board in boards {
-                    state.insert(board.to_owned());
-                    for next in board.next() {
-                        next_boards.insert(next);
-                    }
-                }
-                Continue((next_boards, state, t + 1))
-            },
-        ) {
-            Break((t, _)) => Ok(t),
-            _ => Err(AocError.into()),
+                text.push_str("#\n");
+            }
+            text.push_str(&format!("{}.#\n", "#".repeat(width)));
+            let board = Self::parse(&mut text.as_bytes()).expect("a valley");
+            if board
+                .cross(board.entrance(), board.exit(), 0)
+                .and_then(|t| board.cross(board.exit(), board.entrance(), t))
+                .and_then(|t| board.cross(board.entrance(), board.exit(), t))
+                .is_ok()
+            {
+                return text;
+            }
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day24 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part1() {
-        test1(
-            "#.######
-#>>.<^<#
-#.<..<<#
-#>v.><>#
-#<^v^^>#
-######.#",
-            18,
-        );
-    }
-
-    fn test2(s: &str, f: Output) {
-        assert_eq!(Day24 {}.part2_impl(&mut s.as_bytes()).ok(), Some(f));
-    }
-
-    #[test]
-    fn part2() {
-        test2(
-            "#.######
-#>>.<^<#
-#.<..<<#
-#>v.><>#
-#<^v^^>#
-######.#",
-            54,
-        );
-    }
-}
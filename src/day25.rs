@@ -1,4 +1,7 @@
 use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 pub struct Day25 {}
@@ -10,13 +13,18 @@ impl Day for Day25 {
         "25"
     }
 
-    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>) {
-        println!("{:?}", self.part1_impl(&mut *input()));
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
     }
 }
 
+/// A number in the balanced base-5 SNAFU notation, digits `=-012`.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
-struct Snafu {
+pub struct Snafu {
     inner: i64,
 }
 
@@ -25,14 +33,17 @@ impl FromStr for Snafu {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         s.bytes()
-            .fold(Ok(0i64), |n, b| {
+            .enumerate()
+            .fold(Ok(0i64), |n, (i, b)| {
                 n.and_then(|n| {
                     Ok(n * 5
                         + match b {
                             b'=' => -2,
                             b'-' => -1,
                             b'0' | b'1' | b'2' => (b - b'0') as i64,
-                            _ => Err(AocError)?,
+                            _ => Err(AocError::new(Reason::Parse)
+                                .column(i + 1)
+                                .token(&(b as char)))?,
                         })
                 })
             })
@@ -40,8 +51,8 @@ impl FromStr for Snafu {
     }
 }
 
-impl ToString for Snafu {
-    fn to_string(&self) -> String {
+impl Display for Snafu {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut s = String::new();
         let mut i = self.inner;
         while i != 0 {
@@ -59,7 +70,7 @@ impl ToString for Snafu {
             );
             i = (i - r) / 5;
         }
-        s
+        s.fmt(f)
     }
 }
 
@@ -77,42 +88,27 @@ impl From<Snafu> for i64 {
 
 impl Day25 {
     fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
-        io::BufReader::new(input)
-            .lines()
-            .map(|r| {
-                r.map_err(|e| Box::new(e) as Box<dyn error::Error>)
-                    .and_then(|s| s.parse::<Snafu>().map_err(|e| e.into()).map(i64::from))
-            })
-            .sum::<BoxResult<i64>>()
-            .map(|n| Snafu::from(n).to_string())
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test1(s: &str, f: Output) {
-        assert_eq!(Day25 {}.part1_impl(&mut s.as_bytes()).ok(), Some(f));
+        let numbers = timing::parsing(|| {
+            io::BufReader::new(input)
+                .lines()
+                .enumerate()
+                .map(|(i, r)| {
+                    r.map_err(|e| Box::new(e) as Box<dyn error::Error>)
+                        .and_then(|s| s.parse::<Snafu>().at_line(i + 1).map(i64::from))
+                })
+                .collect::<BoxResult<Vec<_>>>()
+        })?;
+        Ok(Snafu::from(numbers.into_iter().sum::<i64>()).to_string())
     }
 
-    #[test]
-    fn part1() {
-        test1(
-            "1=-0-2
-12111
-2=0=
-21
-2=01
-111
-20012
-112
-1=-1=
-1-12
-12
-1=
-122",
-            "2=-1=0".to_string(),
-        );
+    /// `size` positive numbers of up to 20 digits.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                let digits = rng.range(1..=19);
+                let n = rng.range(1..=5i64.pow(digits));
+                format!("{}\n", Snafu::from(n))
+            })
+            .collect()
     }
 }
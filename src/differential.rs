@@ -0,0 +1,214 @@
+use crate::day::*;
+use crate::input;
+use crate::random::Rng;
+use crate::runner;
+use simple_error::SimpleError;
+use std::fmt;
+
+/// An input on which a part answers differently than its reference, shrunk
+/// until removing any line or lowering any number makes them agree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Disagreement {
+    pub part: usize,
+    pub input: String,
+    pub answer: Answer,
+    pub expected: Answer,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "part {} answers {} instead of {} for\n{}",
+            self.part, self.answer, self.expected, self.input
+        )
+    }
+}
+
+/// The parts of `day` having a reference solution, of those selected.
+pub fn references(day: &dyn Day, part: Option<usize>) -> Vec<usize> {
+    (1..=2)
+        .filter(|&p| part.is_none_or(|part| part == p))
+        .filter(|&p| day.has_reference(p))
+        .collect()
+}
+
+/// Check the parts of `day` against their references on `cases` random
+/// inputs, growing from size 1 to `size`, and return the first input they
+/// disagree on, shrunk.
+pub fn compare(
+    day: &dyn Day,
+    params: &Params,
+    part: Option<usize>,
+    seed: u64,
+    cases: usize,
+    size: usize,
+) -> BoxResult<Option<Disagreement>> {
+    let parts = references(day, part);
+    if parts.is_empty() {
+        Err(SimpleError::new(format!(
+            "day {} has no reference solution",
+            day.tag()
+        )))?
+    }
+    for case in 0..cases {
+        let mut rng = Rng::new(seed).fork(case as u64);
+        let input = day.generate(&mut rng, 1 + case * size / cases, params)?;
+        for &part in &parts {
+            if let Some(found) = disagreement(day, params, part, input.to_owned()) {
+                return Ok(Some(shrink(day, params, found)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn solve<F: FnOnce() -> Answer>(day: &dyn Day, f: F) -> Answer {
+    runner::catching(day.tag(), f)
+}
+
+// Inputs the reference fails on are not valid, and cannot disagree.
+fn disagreement(
+    day: &dyn Day,
+    params: &Params,
+    part: usize,
+    input: String,
+) -> Option<Disagreement> {
+    let (answer, expected) = {
+        let data = input::factory(input.as_bytes());
+        let params = params.for_part(part);
+        let expected = solve(day, || day.reference(part, &data, &params));
+        if let Answer::Error { .. } | Answer::Unimplemented = expected {
+            return None;
+        }
+        let answer = solve(day, || {
+            if part == 1 {
+                day.part1(&data, &params)
+            } else {
+                day.part2(&data, &params)
+            }
+        });
+        (answer, expected)
+    };
+    (answer != expected).then_some(Disagreement {
+        part,
+        input,
+        answer,
+        expected,
+    })
+}
+
+fn shrink(day: &dyn Day, params: &Params, mut found: Disagreement) -> Disagreement {
+    loop {
+        let next =
+            smaller(&found.input).find_map(|input| disagreement(day, params, found.part, input));
+        match next {
+            Some(next) => found = next,
+            None => return found,
+        }
+    }
+}
+
+/// The input without one of its lines, or with one of its numbers lowered
+/// to 0, to half of it or by one.
+fn smaller(input: &str) -> impl Iterator<Item = String> + '_ {
+    let lines = input.lines().collect::<Vec<_>>();
+    let without = (0..lines.len()).map(move |i| {
+        lines
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, line)| format!("{}\n", line))
+            .collect()
+    });
+    let mut numbers = vec![];
+    let mut start = None;
+    for (i, c) in input.char_indices().chain([(input.len(), ' ')]) {
+        match (start, c.is_ascii_digit()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                numbers.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    let lowered = numbers.into_iter().flat_map(move |range| {
+        let n = input[range.to_owned()].parse::<u64>().unwrap_or(0);
+        let mut lower = vec![0, n / 2, n.saturating_sub(1)];
+        lower.dedup();
+        lower
+            .into_iter()
+            .filter(move |&m| m < n)
+            .map(move |m| format!("{}{}{}", &input[..range.start], m, &input[range.end..]))
+    });
+    without.chain(lowered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sums numbers, but skips those above 50 unless solved the slow way.
+    struct Sum {}
+
+    impl Day for Sum {
+        fn tag(&self) -> &str {
+            "01"
+        }
+
+        fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+            Answer::Integer(
+                Utils::numbers(&mut *input())
+                    .map(|n| n.unwrap())
+                    .filter(|&n| n <= 50)
+                    .sum(),
+            )
+        }
+
+        fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+            Answer::Integer(Utils::numbers(&mut *input()).map(|n| n.unwrap()).sum())
+        }
+
+        fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+            Ok((0..size)
+                .map(|_| format!("{}\n", rng.range(0..=100)))
+                .collect())
+        }
+
+        fn has_reference(&self, part: usize) -> bool {
+            part == 1
+        }
+
+        fn reference(
+            &self,
+            _part: usize,
+            input: &dyn Fn() -> Box<dyn io::Read>,
+            params: &Params,
+        ) -> Answer {
+            self.part2(input, params)
+        }
+    }
+
+    #[test]
+    fn differential() {
+        let params = Params::new();
+        assert_eq!(references(&Sum {}, None), vec![1]);
+        assert!(references(&Sum {}, Some(2)).is_empty());
+        assert!(compare(&Sum {}, &params, Some(2), 0, 10, 5).is_err());
+        let found = compare(&Sum {}, &params, None, 0, 10, 5).unwrap().unwrap();
+        assert_eq!(
+            found,
+            Disagreement {
+                part: 1,
+                input: "51\n".to_owned(),
+                answer: Answer::Integer(0),
+                expected: Answer::Integer(51),
+            }
+        );
+        assert_eq!(
+            found.to_string(),
+            "part 1 answers 0 instead of 51 for\n51\n"
+        );
+    }
+}
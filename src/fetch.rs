@@ -0,0 +1,113 @@
+use crate::day::*;
+use std::fs;
+use std::path::Path;
+
+pub const BASE_URL: &str = "https://adventofcode.com";
+
+const USER_AGENT: &str = "github.com/niklasha/adventofcode2022 by niklas@hallqvist.se";
+
+/// Downloads puzzle inputs from `base_url`, which serves them the way the
+/// Advent of Code site does, as `/YEAR/day/DAY/input`.
+pub struct Fetcher {
+    base_url: String,
+    session: String,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Fetched {
+    Downloaded,
+    Cached,
+}
+
+impl Fetcher {
+    pub fn new(base_url: &str, session: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            session: session.to_owned(),
+        }
+    }
+
+    /// Download the input of the given day to `path`, unless it is already
+    /// there.  The input is written to a temporary file first, so a failed
+    /// download never leaves a partial input behind to be mistaken for a
+    /// cached one.
+    pub fn fetch(&self, year: usize, day: usize, path: &str) -> BoxResult<Fetched> {
+        if Path::new(path).exists() {
+            return Ok(Fetched::Cached);
+        }
+        let url = format!("{}/{}/day/{}/input", self.base_url, year, day);
+        let response = ureq::get(&url)
+            .set("Cookie", &format!("session={}", self.session))
+            .set("User-Agent", USER_AGENT)
+            .call()?;
+        let mut data = vec![];
+        io::Read::read_to_end(&mut response.into_reader(), &mut data)?;
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = format!("{}.partial", path);
+        fs::write(&partial, data)
+            .and_then(|_| fs::rename(&partial, path))
+            .map_err(|e| format!("cannot write {}: {}", path, e))?;
+        Ok(Fetched::Downloaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn fetch() {
+        // A stub of the site, serving the input of day 5 only.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = vec![];
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                let (status, body) = match request.split_whitespace().nth(1) {
+                    Some("/2022/day/5/input") => ("200 OK", "5\n"),
+                    _ => ("404 Not Found", "no such day\n"),
+                };
+                log.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("aoc-fetch-{}", std::process::id()));
+        let path = dir.join("2022").join("05").to_string_lossy().into_owned();
+        let fetcher = Fetcher::new(&format!("{}/", base_url), "c0ffee");
+        assert_eq!(fetcher.fetch(2022, 5, &path).unwrap(), Fetched::Downloaded);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "5\n");
+        assert!(requests.lock().unwrap()[0].contains("Cookie: session=c0ffee"));
+        assert_eq!(fetcher.fetch(2022, 5, &path).unwrap(), Fetched::Cached);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        let missing = dir.join("06").to_string_lossy().into_owned();
+        assert!(fetcher.fetch(2022, 6, &missing).is_err());
+        assert!(!Path::new(&missing).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
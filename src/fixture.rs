@@ -0,0 +1,184 @@
+use crate::day::*;
+use crate::input;
+use crate::registry::Registry;
+use simple_error::SimpleError;
+use std::fs;
+use std::path::Path;
+
+/// An example input with its expected answers, kept as a file under
+/// "fixtures/YEAR/DAY/", e.g. "fixtures/2022/15/example.txt":
+///
+/// ```text
+/// # Parameters first, as "NAME = VALUE", or "part2.NAME = VALUE".
+/// row = 10
+/// --- input
+/// Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+/// --- part 1
+/// 26
+/// ```
+///
+/// A part without a section is not checked, and a grid answer has one line
+/// per row.
+#[derive(Debug, Default)]
+pub struct Fixture {
+    pub year: usize,
+    pub day: usize,
+    pub params: Params,
+    pub input: String,
+    pub expected: Vec<(usize, Vec<String>)>,
+}
+
+enum Section {
+    Header,
+    Input,
+    Part(usize),
+}
+
+impl Fixture {
+    /// Load a fixture, taking its year and day from its path.
+    pub fn load(path: &str) -> BoxResult<Self> {
+        let malformed = || SimpleError::new(format!("{} is not under fixtures/YEAR/DAY/", path));
+        let mut dirs = Path::new(path).iter().rev().skip(1);
+        let day = dirs.next().ok_or_else(malformed)?.to_string_lossy();
+        let year = dirs.next().ok_or_else(malformed)?.to_string_lossy();
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        Ok(Self {
+            year: year.parse().map_err(|_| malformed())?,
+            day: day.parse().map_err(|_| malformed())?,
+            ..Self::parse(&text).map_err(|e| format!("{}: {}", path, e))?
+        })
+    }
+
+    pub fn parse(text: &str) -> BoxResult<Self> {
+        let mut fixture = Self::default();
+        let mut section = Section::Header;
+        let mut lines = vec![];
+        for (i, l) in text.lines().enumerate() {
+            if let Some(name) = l.strip_prefix("--- ") {
+                fixture.end(&section, &mut lines);
+                section = match name.trim() {
+                    "input" => Section::Input,
+                    name => Section::Part(
+                        name.strip_prefix("part ")
+                            .and_then(|part| part.parse().ok())
+                            .filter(|part| [1, 2].contains(part))
+                            .ok_or_else(|| {
+                                format!("unknown section {:?} on line {}", name, i + 1)
+                            })?,
+                    ),
+                };
+                continue;
+            }
+            match section {
+                Section::Header => {
+                    let l = l.trim();
+                    if l.is_empty() || l.starts_with('#') {
+                        continue;
+                    }
+                    let (name, value) = l
+                        .split_once('=')
+                        .ok_or_else(|| format!("malformed parameter on line {}", i + 1))?;
+                    fixture.params.set(name.trim(), value.trim());
+                }
+                _ => lines.push(l.to_owned()),
+            }
+        }
+        fixture.end(&section, &mut lines);
+        if fixture.expected.is_empty() {
+            Err(SimpleError::new("no expected answers"))?;
+        }
+        Ok(fixture)
+    }
+
+    fn end(&mut self, section: &Section, lines: &mut Vec<String>) {
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        match section {
+            Section::Header => {}
+            Section::Input => self.input = lines.iter().map(|l| format!("{}\n", l)).collect(),
+            Section::Part(part) => self.expected.push((*part, lines.to_owned())),
+        }
+        lines.clear();
+    }
+
+    /// Solve the fixture with its day from `registry`, describing every part
+    /// answered differently than expected.
+    pub fn verify(&self, registry: &Registry) -> Result<(), String> {
+        let day = registry
+            .get(self.year, self.day)
+            .ok_or_else(|| format!("no day {} of {}", self.day, self.year))?;
+        let declared = day.params();
+        if let Some((name, _)) = self
+            .params
+            .iter()
+            .find(|(name, _)| !declared.declares(name))
+        {
+            return Err(format!("day {} takes no parameter {}", self.day, name));
+        }
+        let input = input::factory(self.input.as_bytes());
+        let failures = self
+            .expected
+            .iter()
+            .filter_map(|(part, expected)| {
+                let params = day.params().overlay(&self.params).for_part(*part);
+                let answer = if *part == 1 {
+                    day.part1(&input, &params)
+                } else {
+                    day.part2(&input, &params)
+                };
+                let actual = answer.lines();
+                (&actual != expected).then(|| {
+                    format!(
+                        "part {}: expected {}, got {}",
+                        part,
+                        expected.join("\n"),
+                        actual.join("\n")
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let fixture = Fixture::parse(
+            "# The sum of the numbers.
+part2.max = 20
+--- input
+1000
+
+2000
+--- part 1
+3000
+
+--- part 2
+#..
+.#.
+",
+        )
+        .unwrap();
+        assert_eq!(fixture.params, Params::new().with("part2.max", "20"));
+        assert_eq!(fixture.input, "1000\n\n2000\n");
+        assert_eq!(
+            fixture.expected,
+            vec![
+                (1, vec!["3000".to_owned()]),
+                (2, vec!["#..".to_owned(), ".#.".to_owned()])
+            ]
+        );
+        assert!(Fixture::parse("--- input\n1\n").is_err());
+        assert!(Fixture::parse("--- part 3\n1\n").is_err());
+        assert!(Fixture::parse("row\n--- part 1\n1\n").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::day::*;
+use num::Signed;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// A point, or a vector, in the plane.  The y axis points down, as on the
+/// screen, so `Dir::Up` decreases y.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+/// A point, or a vector, in space.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+/// One of the four directions in the plane, clockwise from up.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Dir {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+/// A quarter turn.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+impl<T> Point2<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Signed + Copy + Ord> Point2<T> {
+    pub fn manhattan(&self, other: &Self) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn chebyshev(&self, other: &Self) -> T {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    /// The vector with each coordinate replaced by its sign, i.e. a single
+    /// step, diagonal or not, in the vector's general direction.
+    pub fn signum(&self) -> Self {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+
+    /// The four orthogonal neighbours, clockwise from up.
+    pub fn neighbours4(&self) -> [Self; 4] {
+        Dir::ALL.map(|dir| *self + dir)
+    }
+
+    /// All eight neighbours, clockwise from up.
+    pub fn neighbours8(&self) -> [Self; 8] {
+        Dir::ALL
+            .map(|dir| [*self + dir, *self + dir + dir.turn_right()])
+            .concat()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!())
+    }
+}
+
+impl Point2<usize> {
+    /// The point one step away, unless that is left of or above the origin.
+    pub fn checked_step(&self, dir: Dir) -> Option<Self> {
+        let step = dir.step::<isize>();
+        Some(Self::new(
+            self.x.checked_add_signed(step.x)?,
+            self.y.checked_add_signed(step.y)?,
+        ))
+    }
+}
+
+impl<T> Point3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl<T: Signed + Copy + Ord> Point3<T> {
+    /// The six unit vectors along the axes, negative one first.
+    pub fn units() -> [Self; 6] {
+        let (o, i) = (T::zero(), T::one());
+        [
+            Self::new(-i, o, o),
+            Self::new(i, o, o),
+            Self::new(o, -i, o),
+            Self::new(o, i, o),
+            Self::new(o, o, -i),
+            Self::new(o, o, i),
+        ]
+    }
+
+    pub fn manhattan(&self, other: &Self) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+
+    pub fn chebyshev(&self, other: &Self) -> T {
+        (self.x - other.x)
+            .abs()
+            .max((self.y - other.y).abs())
+            .max((self.z - other.z).abs())
+    }
+
+    /// The six neighbours sharing a face.
+    pub fn neighbours6(&self) -> [Self; 6] {
+        Self::units().map(|unit| *self + unit)
+    }
+}
+
+impl Dir {
+    pub const ALL: [Self; 4] = [Self::Up, Self::Right, Self::Down, Self::Left];
+
+    pub fn turn(self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Right => self.turn_right(),
+        }
+    }
+
+    pub fn turn_left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn turn_right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self as usize + 2) % 4]
+    }
+
+    /// The unit vector pointing this way.
+    pub fn step<T: Signed>(self) -> Point2<T> {
+        let (o, i) = (T::zero(), T::one());
+        match self {
+            Self::Up => Point2::new(o, -i),
+            Self::Right => Point2::new(i, o),
+            Self::Down => Point2::new(o, i),
+            Self::Left => Point2::new(-i, o),
+        }
+    }
+}
+
+/// A direction is written as a letter, "U", "R", "D" or "L", or as an arrow,
+/// "^", ">", "v" or "<".
+impl FromStr for Dir {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "U" | "^" => Self::Up,
+            "R" | ">" => Self::Right,
+            "D" | "v" => Self::Down,
+            "L" | "<" => Self::Left,
+            _ => Err(AocError::new(Reason::Parse).token(s))?,
+        })
+    }
+}
+
+/// Parse exactly `N` comma-separated coordinates.
+fn coordinates<T, const N: usize>(s: &str) -> BoxResult<[T; N]>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn error::Error>>,
+{
+    let coordinates = s
+        .split(',')
+        .map(|t| t.trim().parse().with_token(s))
+        .collect::<BoxResult<Vec<T>>>()?;
+    let n = coordinates.len();
+    coordinates.try_into().or_else(|_| {
+        let reason = if n < N {
+            Reason::MissingField
+        } else {
+            Reason::Parse
+        };
+        Err(AocError::new(reason).token(s))?
+    })
+}
+
+impl<T> FromStr for Point2<T>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn error::Error>>,
+{
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y] = coordinates(s)?;
+        Ok(Self::new(x, y))
+    }
+}
+
+impl<T> FromStr for Point3<T>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn error::Error>>,
+{
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y, z] = coordinates(s)?;
+        Ok(Self::new(x, y, z))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point2<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point3<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point2<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Signed> Add<Dir> for Point2<T> {
+    type Output = Self;
+
+    fn add(self, dir: Dir) -> Self {
+        self + dir.step()
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point2<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point2<T> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        Self::new(self.x * k, self.y * k)
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point2<T> {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point3<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point3<T> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        Self::new(self.x * k, self.y * k, self.z * k)
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point3<T> {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points() {
+        let p = "3,-4".parse::<Point2<i64>>().unwrap();
+        assert_eq!(p, Point2::new(3, -4));
+        assert_eq!(p.to_string(), "3,-4");
+        assert_eq!(p.manhattan(&Point2::default()), 7);
+        assert_eq!(p.chebyshev(&Point2::default()), 4);
+        assert_eq!(p - Point2::new(1, 1), Point2::new(2, -5));
+        assert_eq!(-p * 2, Point2::new(-6, 8));
+        assert_eq!(p.signum(), Point2::new(1, -1));
+        assert_eq!(p + Dir::Up, Point2::new(3, -5));
+        assert_eq!(p.neighbours4()[1], Point2::new(4, -4));
+        assert_eq!(p.neighbours8()[1], Point2::new(4, -5));
+        assert_eq!(Point2::new(0usize, 1).checked_step(Dir::Left), None);
+        assert_eq!(
+            Point2::new(0usize, 1).checked_step(Dir::Up),
+            Some(Point2::new(0, 0))
+        );
+        assert!("3".parse::<Point2<i64>>().is_err());
+        assert!("3,4,5".parse::<Point2<i64>>().is_err());
+        assert!("3,x".parse::<Point2<i64>>().is_err());
+
+        let q = "1,2,3".parse::<Point3<i64>>().unwrap();
+        assert_eq!(q.manhattan(&Point3::new(0, 0, 0)), 6);
+        assert_eq!(q.chebyshev(&Point3::new(0, 0, 0)), 3);
+        assert_eq!(q.neighbours6().len(), 6);
+        assert!(q.neighbours6().iter().all(|n| n.manhattan(&q) == 1));
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Dir::Up.turn_right(), Dir::Right);
+        assert_eq!(Dir::Up.turn_left(), Dir::Left);
+        assert_eq!(Dir::Left.turn(Turn::Right), Dir::Up);
+        assert_eq!(Dir::Down.reverse(), Dir::Up);
+        assert_eq!("v".parse::<Dir>().unwrap(), Dir::Down);
+        assert_eq!("L".parse::<Dir>().unwrap(), Dir::Left);
+        assert!("X".parse::<Dir>().is_err());
+        assert_eq!(Dir::Right.step::<i64>(), Point2::new(1, 0));
+    }
+}
@@ -0,0 +1,349 @@
+use crate::day::*;
+use std::fmt;
+use std::iter;
+use std::ops::{Index, IndexMut};
+
+/// A position in a grid, as (row, column) from the top left corner.
+pub type Pos = (usize, usize);
+
+/// The steps, as (rows, columns), to the four orthogonal neighbours,
+/// clockwise from up.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// The steps to all eight neighbours, clockwise from up.
+pub const ADJACENT: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// A rectangular grid of cells, stored row by row.  Indexing with a
+/// position panics when out of bounds, while `get` and friends check.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parse a grid from text, one row per line, mapping every character to
+    /// a cell.  A character mapped to `None`, or a line longer or shorter
+    /// than the first, is a parse error.
+    pub fn parse<F>(input: &mut dyn io::Read, cell: F) -> BoxResult<Self>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let lines = io::BufReader::new(input)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_lines(&lines, None, cell)
+    }
+
+    /// Build a grid from lines of text.  If `pad` is given, lines shorter
+    /// than the longest one are padded with it, else they are an error.
+    pub fn from_lines<S, F>(lines: &[S], pad: Option<char>, mut cell: F) -> BoxResult<Self>
+    where
+        S: AsRef<str>,
+        F: FnMut(char) -> Option<T>,
+    {
+        let lengths = lines.iter().map(|l| l.as_ref().chars().count());
+        let width = match pad {
+            Some(_) => lengths.max().unwrap_or(0),
+            None => lengths.take(1).sum(),
+        };
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (i, l) in lines.iter().enumerate() {
+            let l = l.as_ref();
+            let length = l.chars().count();
+            if length != width && (pad.is_none() || length > width) {
+                Err(AocError::new(Reason::Parse)
+                    .line(i + 1)
+                    .column(length.min(width) + 1)
+                    .token(l))?
+            }
+            let padding = iter::repeat_n(pad.unwrap_or(' '), width - length);
+            for (j, c) in l.chars().chain(padding).enumerate() {
+                cells.push(
+                    cell(c)
+                        .with_reason(Reason::Parse)
+                        .at_line(i + 1)
+                        .at_column(j + 1)
+                        .with_token(&c)?,
+                );
+            }
+        }
+        Ok(Self {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.0 < self.height && pos.1 < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) {
+            self.cells.get(pos.0 * self.width + pos.1)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            self.cells.get_mut(pos.0 * self.width + pos.1)
+        } else {
+            None
+        }
+    }
+
+    /// The position one step away from `pos`, unless that is off the grid.
+    pub fn step(&self, pos: Pos, step: (isize, isize)) -> Option<Pos> {
+        let next = (
+            pos.0.checked_add_signed(step.0)?,
+            pos.1.checked_add_signed(step.1)?,
+        );
+        self.contains(next).then_some(next)
+    }
+
+    /// The positions of the orthogonal neighbours on the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .filter_map(move |step| self.step(pos, step))
+    }
+
+    /// The positions of all neighbours on the grid, diagonal ones included.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ADJACENT
+            .into_iter()
+            .filter_map(move |step| self.step(pos, step))
+    }
+
+    /// The positions from `pos`, exclusive, repeatedly stepping in the given
+    /// direction until the edge of the grid.
+    pub fn ray(&self, pos: Pos, step: (isize, isize)) -> impl Iterator<Item = Pos> + '_ {
+        iter::successors(self.step(pos, step), move |&pos| self.step(pos, step))
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        (row < self.height).then(|| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> Option<&mut [T]> {
+        (row < self.height).then(|| &mut self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + ExactSizeIterator {
+        (0..self.height).map(|row| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    /// The cells of a column, top to bottom, none if it is off the grid.
+    pub fn column(&self, column: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        let rows = if column < self.width { self.height } else { 0 };
+        (0..rows).map(move |row| &self.cells[row * self.width + column])
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |column| (row, column)))
+    }
+
+    /// All cells with their positions, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The position of the first cell, row by row, satisfying `predicate`.
+    pub fn position<P: FnMut(&T) -> bool>(&self, mut predicate: P) -> Option<Pos> {
+        self.iter()
+            .find(|(_, cell)| predicate(cell))
+            .map(|(pos, _)| pos)
+    }
+
+    /// Add a row at the bottom.  An empty grid takes its width from the row.
+    pub fn push_row(&mut self, row: Vec<T>) {
+        if self.height == 0 {
+            self.width = row.len();
+        }
+        assert_eq!(row.len(), self.width, "row width differs from the grid's");
+        self.cells.extend(row);
+        self.height += 1;
+    }
+
+    /// Remove the bottom row.
+    pub fn pop_row(&mut self) -> Option<Vec<T>> {
+        if self.height == 0 {
+            return None;
+        }
+        self.height -= 1;
+        Some(self.cells.split_off(self.height * self.width))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Mirror the grid in its main diagonal, turning rows into columns.
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.rearrange(self.height, self.width, |(row, column)| (column, row))
+    }
+
+    /// Rotate the grid a quarter turn clockwise.
+    pub fn rotate_right(&self) -> Self
+    where
+        T: Clone,
+    {
+        let height = self.height;
+        self.rearrange(height, self.width, |(row, column)| {
+            (height - 1 - column, row)
+        })
+    }
+
+    /// Rotate the grid a quarter turn counterclockwise.
+    pub fn rotate_left(&self) -> Self
+    where
+        T: Clone,
+    {
+        let width = self.width;
+        self.rearrange(self.height, width, |(row, column)| {
+            (column, width - 1 - row)
+        })
+    }
+
+    /// A grid of the given size whose cells are taken from the positions of
+    /// this one that `source` gives.
+    fn rearrange<F: Fn(Pos) -> Pos>(&self, width: usize, height: usize, source: F) -> Self
+    where
+        T: Clone,
+    {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(|pos| self[source(pos)].to_owned())
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Render the grid as text, one line per row, mapping every cell to a
+    /// character.
+    pub fn render<F: FnMut(&T) -> char>(&self, mut cell: F) -> String {
+        let mut s = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            s.extend(row.iter().map(&mut cell));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+/// A grid of characters, or of bytes, renders as the text it was parsed from.
+impl<T: Copy + Into<char>> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|&cell| cell.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let grid = Grid::parse(&mut "#..\n.#.\n".as_bytes(), |c| Some(c as u8)).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 1)], b'#');
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.to_string(), "#..\n.#.\n");
+        let e = Grid::parse(&mut "#.\n.x\n".as_bytes(), |c| (c != 'x').then_some(c))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(e, "parse failure at line 2, column 2 near \"x\"");
+        assert!(Grid::parse(&mut "#.\n.\n".as_bytes(), Some).is_err());
+        let padded = Grid::from_lines(&["  #", "#"], Some(' '), Some).unwrap();
+        assert_eq!(padded.to_string(), "  #\n#  \n");
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::new(3, 3, '.');
+        assert_eq!(grid.neighbours4((0, 0)).collect_vec(), vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours4((1, 1)).count(), 4);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+        assert_eq!(grid.neighbours8((2, 2)).count(), 3);
+        assert_eq!(grid.ray((0, 1), (1, 0)).collect_vec(), vec![(1, 1), (2, 1)]);
+        assert_eq!(grid.ray((0, 1), (-1, 0)).count(), 0);
+        assert_eq!(grid.step((0, 0), (0, -1)), None);
+    }
+
+    #[test]
+    fn rearrange() {
+        let mut grid = Grid::parse(&mut "abc\ndef\n".as_bytes(), Some).unwrap();
+        assert_eq!(grid.row(1), Some(&['d', 'e', 'f'][..]));
+        assert_eq!(grid.column(2).collect::<String>(), "cf");
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.position(|&c| c == 'e'), Some((1, 1)));
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_right().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_left().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.rotate_left().rotate_right(), grid);
+        grid.push_row(vec!['g', 'h', 'i']);
+        grid[(2, 0)] = 'G';
+        assert_eq!(grid.pop_row(), Some(vec!['G', 'h', 'i']));
+        assert_eq!(grid.map(|&c| c == 'e').position(|&e| e), Some((1, 1)));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::day::*;
+use crate::YEAR;
+use flate2::read::MultiGzDecoder;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Where a day's input is read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Source {
+    /// A file, transparently decompressed if it is gzipped.
+    File(String),
+    Stdin,
+    Inline(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path),
+            Self::Stdin => write!(f, "-"),
+            Self::Inline(_) => write!(f, "(inline)"),
+        }
+    }
+}
+
+impl Source {
+    /// The source of a day's input given an input spec: "-" is stdin, a spec
+    /// containing "{year}" or "{day}" has them replaced by the year and the
+    /// day's tag, e.g. "inputs/{year}/day{day}.txt", and any other spec is a
+    /// prefix of per-year directories, e.g. "input/2020/08".  The inputs of
+    /// the crate's own year may also be directly under the prefix, e.g.
+    /// "input/08", if there is no directory for the year.  If the file does
+    /// not exist but a gzipped one, with a ".gz" suffix, does, that one is
+    /// used.
+    pub fn resolve(spec: &str, year: usize, tag: &str) -> Self {
+        if spec == "-" {
+            return Self::Stdin;
+        }
+        let path = if spec.contains("{year}") || spec.contains("{day}") {
+            spec.replace("{year}", &year.to_string())
+                .replace("{day}", tag)
+        } else if year == YEAR && !Path::new(&format!("{}{}", spec, year)).is_dir() {
+            format!("{}{}", spec, tag)
+        } else {
+            format!("{}{}/{}", spec, year, tag)
+        };
+        let gz = format!("{}.gz", path);
+        Self::File(if !Path::new(&path).exists() && Path::new(&gz).exists() {
+            gz
+        } else {
+            path
+        })
+    }
+
+    pub fn exists(&self) -> bool {
+        match self {
+            Self::File(path) => Path::new(path).is_file(),
+            _ => true,
+        }
+    }
+
+    pub fn open(&self) -> BoxResult<Box<dyn io::Read>> {
+        Ok(match self {
+            Self::File(path) => {
+                let mut file = io::BufReader::new(fs::File::open(path)?);
+                if file.fill_buf()?.starts_with(GZIP_MAGIC) {
+                    Box::new(MultiGzDecoder::new(file))
+                } else {
+                    Box::new(file)
+                }
+            }
+            Self::Stdin => Box::new(io::stdin()),
+            Self::Inline(s) => Box::new(io::Cursor::new(s.to_owned().into_bytes())),
+        })
+    }
+
+    /// Read all of the input into memory.
+    pub fn read(&self) -> BoxResult<Vec<u8>> {
+        let mut data = vec![];
+        // Sized up front, where the size is known, so that the buffer is not
+        // grown to twice what it holds.
+        if let Self::File(path) = self {
+            data.reserve(fs::metadata(path).map_or(0, |m| m.len() as usize));
+        }
+        self.open()
+            .and_then(|mut input| Ok(input.read_to_end(&mut data)?))
+            .map_err(|e| format!("cannot open {}: {}", self, e))?;
+        Ok(data)
+    }
+}
+
+/// An input factory, as taken by `Day::part1` and `Day::part2`, handing out
+/// a fresh reader over `data` on every call.
+pub fn factory(data: &[u8]) -> impl Fn() -> Box<dyn io::Read> + '_ {
+    || Box::new(io::Cursor::new(data.to_vec()))
+}
+
+/// The contents of an input, read into memory once and shared by all of
+/// its readers rather than copied for each.
+#[derive(Clone)]
+pub struct Shared(Arc<Vec<u8>>);
+
+impl Shared {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self(Arc::new(data))
+    }
+}
+
+impl AsRef<[u8]> for Shared {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// An input factory like `factory`, but whose readers share `data`.
+pub fn shared(data: &Shared) -> impl Fn() -> Box<dyn io::Read> + '_ {
+    || Box::new(io::Cursor::new(data.clone()))
+}
+
+/// An input factory opening `source` afresh on every call, so that the
+/// input is read as it is solved and never held whole.  A source that can no
+/// longer be opened gives a reader failing with why.
+pub fn streamed(source: &Source) -> impl Fn() -> Box<dyn io::Read> + '_ {
+    move || {
+        source
+            .open()
+            .unwrap_or_else(|e| Box::new(Unreadable(format!("cannot open {}: {}", source, e))))
+    }
+}
+
+struct Unreadable(String);
+
+impl io::Read for Unreadable {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other(self.0.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn sources() {
+        let dir = std::env::temp_dir().join(format!("aoc-input-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let prefix = format!("{}/", dir.to_string_lossy());
+        fs::write(dir.join("01"), "plain\n").unwrap();
+        let mut gz = GzEncoder::new(
+            fs::File::create(dir.join("02.gz")).unwrap(),
+            Compression::default(),
+        );
+        gz.write_all(b"compressed\n").unwrap();
+        gz.finish().unwrap();
+        fs::create_dir_all(dir.join("2020")).unwrap();
+        fs::write(dir.join("2020").join("day03.txt"), "layout\n").unwrap();
+
+        let plain = Source::resolve(&prefix, YEAR, "01");
+        assert_eq!(plain, Source::File(format!("{}01", prefix)));
+        assert_eq!(plain.read().unwrap(), b"plain\n");
+        let compressed = Source::resolve(&prefix, YEAR, "02");
+        assert_eq!(compressed, Source::File(format!("{}02.gz", prefix)));
+        assert_eq!(compressed.read().unwrap(), b"compressed\n");
+        let layout = Source::resolve(&format!("{}{{year}}/day{{day}}.txt", prefix), 2020, "03");
+        assert_eq!(layout.read().unwrap(), b"layout\n");
+        let other_year = Source::resolve(&prefix, 2020, "01");
+        assert_eq!(other_year, Source::File(format!("{}2020/01", prefix)));
+        let missing = Source::resolve(&prefix, YEAR, "04");
+        assert!(!missing.exists());
+        assert!(missing
+            .read()
+            .unwrap_err()
+            .to_string()
+            .starts_with("cannot open"));
+        assert_eq!(Source::resolve("-", YEAR, "05"), Source::Stdin);
+        let inline = Source::Inline("inline\n".to_owned());
+        assert_eq!(inline.read().unwrap(), b"inline\n");
+
+        let data = inline.read().unwrap();
+        let contents = Shared::new(data.to_owned());
+        for (input, expected) in [
+            (
+                &factory(&data) as &dyn Fn() -> Box<dyn io::Read>,
+                "inline\n",
+            ),
+            (&shared(&contents), "inline\n"),
+            (&streamed(&compressed), "compressed\n"),
+        ] {
+            for _ in 0..2 {
+                let mut s = String::new();
+                input().read_to_string(&mut s).unwrap();
+                assert_eq!(s, expected);
+            }
+        }
+        let mut s = String::new();
+        let error = streamed(&missing)().read_to_string(&mut s).unwrap_err();
+        assert!(error.to_string().starts_with("cannot open"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// A set of integers, kept as disjoint, non-adjacent inclusive ranges
+/// ordered by their start, so that inserting, removing and looking up a
+/// range takes logarithmic time in the number of ranges.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct IntervalSet {
+    ranges: BTreeMap<i64, i64>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a range, merging it with those it overlaps or touches.
+    pub fn insert(&mut self, range: RangeInclusive<i64>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        while let Some((&s, &e)) = self.ranges.range(..=end.saturating_add(1)).next_back() {
+            if e < start.saturating_sub(1) {
+                break;
+            }
+            self.ranges.remove(&s);
+            start = start.min(s);
+            end = end.max(e);
+        }
+        self.ranges.insert(start, end);
+    }
+
+    /// Remove a range, splitting those it only partly overlaps.
+    pub fn remove(&mut self, range: RangeInclusive<i64>) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        let overlapping = self.overlapping(start, end).collect::<Vec<_>>();
+        for (s, e) in overlapping {
+            self.ranges.remove(&s);
+            if s < start {
+                self.ranges.insert(s, start.saturating_sub(1));
+            }
+            if e > end {
+                self.ranges.insert(end.saturating_add(1), e);
+            }
+        }
+    }
+
+    /// The ranges overlapping start..=end, last first.
+    fn overlapping(&self, start: i64, end: i64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.ranges
+            .range(..=end)
+            .rev()
+            .take_while(move |(_, &e)| e >= start)
+            .map(|(&s, &e)| (s, e))
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.contains_range(&(x..=x))
+    }
+
+    /// Whether all of the range is in the set.
+    pub fn contains_range(&self, range: &RangeInclusive<i64>) -> bool {
+        range.is_empty()
+            || self
+                .ranges
+                .range(..=range.start())
+                .next_back()
+                .is_some_and(|(_, e)| e >= range.end())
+    }
+
+    /// Whether any of the range is in the set.
+    pub fn overlaps(&self, range: &RangeInclusive<i64>) -> bool {
+        self.overlapping(*range.start(), *range.end())
+            .next()
+            .is_some()
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.iter().all(|range| self.contains_range(&range))
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        other.iter().all(|range| !self.overlaps(&range))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of integers in the set, or `usize::MAX` if there are more.
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|(s, e)| (e.abs_diff(*s) as usize).saturating_add(1))
+            .fold(0, usize::saturating_add)
+    }
+
+    /// The ranges, in order.
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<i64>> + '_ {
+        self.ranges.iter().map(|(&s, &e)| s..=e)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.to_owned();
+        for range in other.iter() {
+            union.insert(range);
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        other
+            .iter()
+            .flat_map(|range| {
+                let (start, end) = range.into_inner();
+                self.overlapping(start, end)
+                    .map(move |(s, e)| s.max(start)..=e.min(end))
+            })
+            .collect()
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = self.to_owned();
+        for range in other.iter() {
+            difference.remove(range);
+        }
+        difference
+    }
+
+    /// The integers within `bounds` not in the set.
+    pub fn complement(&self, bounds: RangeInclusive<i64>) -> Self {
+        Self::from(bounds).difference(self)
+    }
+
+    /// The ranges within `bounds` not in the set, in order.
+    pub fn gaps(&self, bounds: RangeInclusive<i64>) -> impl Iterator<Item = RangeInclusive<i64>> {
+        self.complement(bounds)
+            .ranges
+            .into_iter()
+            .map(|(s, e)| s..=e)
+    }
+}
+
+impl From<RangeInclusive<i64>> for IntervalSet {
+    fn from(range: RangeInclusive<i64>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<i64>>>(ranges: I) -> Self {
+        let mut set = Self::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = IntervalSet::new();
+        set.insert(10..=12);
+        set.insert(1..=3);
+        let (start, end) = (5, 4);
+        set.insert(start..=end);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..=3, 10..=12]);
+        set.insert(4..=6);
+        set.insert(8..=9);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..=6, 8..=12]);
+        set.insert(0..=20);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0..=20]);
+        set.remove(5..=7);
+        set.remove(20..=30);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0..=4, 8..=19]);
+        assert_eq!(set.len(), 17);
+        assert!(set.contains(4) && !set.contains(5) && set.contains(19));
+        assert!(set.contains_range(&(8..=19)) && !set.contains_range(&(3..=8)));
+        assert!(set.overlaps(&(3..=8)) && !set.overlaps(&(5..=7)));
+    }
+
+    #[test]
+    fn bounds() {
+        let mut set = IntervalSet::from(i64::MIN..=i64::MAX);
+        assert_eq!(set.len(), usize::MAX);
+        set.remove(0..=0);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![i64::MIN..=-1, 1..=i64::MAX]
+        );
+        assert_eq!(set.len(), usize::MAX);
+        set.remove(i64::MIN..=-1);
+        set.remove(i64::MAX..=i64::MAX);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1..=i64::MAX - 1]);
+        assert_eq!(set.len(), i64::MAX as usize - 1);
+        set.insert(i64::MIN..=i64::MIN);
+        set.insert(i64::MAX..=i64::MAX);
+        assert_eq!(set.len(), i64::MAX as usize + 1);
+        assert!(set.contains(i64::MIN) && !set.contains(0) && set.contains(i64::MAX));
+        assert_eq!(
+            set.gaps(i64::MIN..=i64::MAX).collect::<Vec<_>>(),
+            vec![i64::MIN + 1..=0]
+        );
+    }
+
+    #[test]
+    fn operations() {
+        let a = IntervalSet::from_iter([1..=5, 10..=15]);
+        let b = IntervalSet::from_iter([4..=11, 20..=20]);
+        let ranges = |set: IntervalSet| set.iter().collect::<Vec<_>>();
+        assert_eq!(ranges(a.union(&b)), vec![1..=15, 20..=20]);
+        assert_eq!(ranges(a.intersection(&b)), vec![4..=5, 10..=11]);
+        assert_eq!(ranges(a.difference(&b)), vec![1..=3, 12..=15]);
+        assert_eq!(ranges(a.complement(0..=12)), vec![0..=0, 6..=9]);
+        assert_eq!(a.gaps(3..=30).collect::<Vec<_>>(), vec![6..=9, 16..=30]);
+        assert!(a.is_superset(&IntervalSet::from(11..=14)));
+        assert!(!a.is_superset(&b));
+        assert!(a.is_disjoint(&IntervalSet::from(6..=9)));
+        assert!(!a.is_disjoint(&b));
+    }
+}
@@ -0,0 +1,93 @@
+#![allow(clippy::manual_try_fold, clippy::too_many_arguments)]
+
+//#[macro_use]
+extern crate closure;
+//#[macro_use]
+extern crate lazy_static;
+extern crate nalgebra as na;
+//#[macro_use]
+extern crate simple_error;
+
+pub mod answers;
+pub mod cancel;
+pub mod config;
+pub mod cycle;
+pub mod day;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod differential;
+pub mod fetch;
+pub mod fixture;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod interval;
+pub mod pattern;
+pub mod random;
+pub mod registry;
+pub mod report;
+pub mod runner;
+pub mod search;
+pub mod timing;
+pub mod trace;
+pub mod visual;
+pub mod y2020;
+
+use crate::registry::Registry;
+
+/// The year this crate was written for, whose days are selected by default.
+pub const YEAR: usize = 2022;
+
+/// All the days of all the years.
+pub fn registry() -> Registry {
+    Registry::new()
+        .add(2020, Box::new(y2020::day08::Day08 {}))
+        .add(YEAR, Box::new(day01::Day01 {}))
+        .add(YEAR, Box::new(day02::Day02 {}))
+        .add(YEAR, Box::new(day03::Day03 {}))
+        .add(YEAR, Box::new(day04::Day04 {}))
+        .add(YEAR, Box::new(day05::Day05 {}))
+        .add(YEAR, Box::new(day06::Day06 {}))
+        .add(YEAR, Box::new(day07::Day07 {}))
+        .add(YEAR, Box::new(day08::Day08 {}))
+        .add(YEAR, Box::new(day09::Day09 {}))
+        .add(YEAR, Box::new(day10::Day10 {}))
+        .add(YEAR, Box::new(day11::Day11 {}))
+        .add(YEAR, Box::new(day12::Day12 {}))
+        .add(YEAR, Box::new(day13::Day13 {}))
+        .add(YEAR, Box::new(day14::Day14 {}))
+        .add(YEAR, Box::new(day15::Day15 {}))
+        .add(YEAR, Box::new(day16::Day16 {}))
+        .add(YEAR, Box::new(day17::Day17 {}))
+        .add(YEAR, Box::new(day18::Day18 {}))
+        .add(YEAR, Box::new(day19::Day19 {}))
+        .add(YEAR, Box::new(day20::Day20 {}))
+        .add(YEAR, Box::new(day21::Day21 {}))
+        .add(YEAR, Box::new(day22::Day22 {}))
+        .add(YEAR, Box::new(day23::Day23 {}))
+        .add(YEAR, Box::new(day24::Day24 {}))
+        .add(YEAR, Box::new(day25::Day25 {}))
+}
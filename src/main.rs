@@ -1,88 +1,347 @@
-//#[macro_use]
-extern crate closure;
-//#[macro_use]
-extern crate lazy_static;
-extern crate nalgebra as na;
-//#[macro_use]
-extern crate simple_error;
+mod cli;
 
-mod cpu;
-mod day;
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-
-use crate::day::*;
+use crate::cli::{Args, Command, Override, USAGE};
+use adventofcode2022::answers::{Answers, Verdict};
+use adventofcode2022::config::Config;
+use adventofcode2022::day::*;
+use adventofcode2022::differential;
+use adventofcode2022::fetch::{self, Fetched, Fetcher};
+use adventofcode2022::input::Source;
+use adventofcode2022::random::Rng;
+use adventofcode2022::report::{self, Format, Record};
+use adventofcode2022::runner::{self, Entry};
+use adventofcode2022::YEAR;
 use std::env;
 use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
 
 fn main() {
-    let args = env::args().collect::<Vec<_>>();
-    let prefix = &args[1];
-    let days: Vec<Box<dyn Day>> = vec![
-        Box::new(day01::Day01 {}),
-        Box::new(day02::Day02 {}),
-        Box::new(day03::Day03 {}),
-        Box::new(day04::Day04 {}),
-        Box::new(day05::Day05 {}),
-        Box::new(day06::Day06 {}),
-        Box::new(day07::Day07 {}),
-        Box::new(day08::Day08 {}),
-        Box::new(day09::Day09 {}),
-        Box::new(day10::Day10 {}),
-        Box::new(day11::Day11 {}),
-        Box::new(day12::Day12 {}),
-        Box::new(day13::Day13 {}),
-        Box::new(day14::Day14 {}),
-        Box::new(day15::Day15 {}),
-        Box::new(day16::Day16 {}),
-        Box::new(day17::Day17 {}),
-        Box::new(day18::Day18 {}),
-        Box::new(day19::Day19 {}),
-        Box::new(day20::Day20 {}),
-        Box::new(day21::Day21 {}),
-        Box::new(day22::Day22 {}),
-        Box::new(day23::Day23 {}),
-        Box::new(day24::Day24 {}),
-        Box::new(day25::Day25 {}),
-    ];
-    let inputs = days.iter().map(|day| format!("{}{}", prefix, day.tag()));
-    for day in days.iter().zip(inputs).rev() {
-        if args.len() > 2 && args[2] != day.0.tag() {
-            continue;
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let config = match &args.config {
+        Some(path) => Config::load(path),
+        None if Path::new("aoc.conf").exists() => Config::load("aoc.conf"),
+        None => Ok(Config::default()),
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let solving = matches!(args.command, Command::Run | Command::Bench | Command::Check);
+    // Stdin can only be read once, so its contents are shared by all days.
+    let stdin = if args.prefix == "-" && args.inline.is_none() && solving {
+        match io::read_to_string(io::stdin()) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!("cannot read stdin: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    // Parameter overrides from the configuration, then from the command line.
+    let overrides = config
+        .iter()
+        .filter(|(key, _)| key.starts_with("day"))
+        .sorted()
+        .map(|(key, value)| Override::parse(key, value))
+        .collect::<BoxResult<Vec<_>>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        })
+        .into_iter()
+        .chain(args.params.to_owned())
+        .collect::<Vec<_>>();
+    // Leaked, as a part that timed out keeps its day until it stops, which a
+    // day that never checks for cancellation only does once it is done.
+    let registry = Box::leak(Box::new(adventofcode2022::registry()));
+    let days = registry
+        .iter()
+        .filter(|&((year, n), _)| args.days.contains(year, n, args.year))
+        .map(|((year, n), day)| {
+            // The crate's own days keep their plain tags, e.g. in answers.
+            let label = if year == YEAR {
+                day.tag().to_owned()
+            } else {
+                format!("{}:{}", year, day.tag())
+            };
+            let source = match args.inline.as_ref().or(stdin.as_ref()) {
+                Some(text) => Source::Inline(text.to_owned()),
+                None => Source::resolve(&args.prefix, year, day.tag()),
+            };
+            let mut params = match args.command {
+                Command::Crosscheck => day.params().overlay(&day.reference_params()),
+                _ => day.params(),
+            };
+            for o in overrides
+                .iter()
+                .filter(|o| o.days.contains(year, n, args.year))
+            {
+                if !params.declares(&o.name) {
+                    eprintln!("day {} takes no parameter {}", label, o.name);
+                    process::exit(2);
+                }
+                params = params.overlay(&Params::new().with(&o.name, &o.value));
+            }
+            let trace = args
+                .trace
+                .iter()
+                .filter(|(days, _)| days.contains(year, n, args.year))
+                .map(|&(_, level)| level)
+                .max()
+                .unwrap_or(0);
+            (
+                (year, n),
+                Entry {
+                    label,
+                    day,
+                    source,
+                    params,
+                    trace,
+                    visualize: args.visualize.to_owned(),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    if days.is_empty() && args.command != Command::Help {
+        eprintln!(
+            "no days selected, the years solved are {:?}",
+            registry.years()
+        );
+        process::exit(2);
+    }
+    match args.command {
+        Command::Help => println!("{}", USAGE),
+        Command::List => {
+            for (_, entry) in days {
+                let status = if entry.source.exists() {
+                    ""
+                } else {
+                    " (missing)"
+                };
+                let params = entry.params.to_string();
+                let params = if params.is_empty() {
+                    params
+                } else {
+                    format!(" {}", params)
+                };
+                println!("{} {}{}{}", entry.label, entry.source, status, params);
+            }
+        }
+        Command::Fetch => {
+            let Some(session) = config.setting("session", "AOC_SESSION") else {
+                eprintln!("no session token, set AOC_SESSION or the session setting");
+                process::exit(1);
+            };
+            let base_url = config
+                .setting("base_url", "AOC_BASE_URL")
+                .unwrap_or(fetch::BASE_URL.to_owned());
+            let fetcher = Fetcher::new(&base_url, &session);
+            let mut failed = false;
+            for ((year, n), entry) in days {
+                let result = match &entry.source {
+                    Source::File(path) => fetcher.fetch(year, n, path),
+                    source => Err(format!("cannot fetch into {}", source).into()),
+                };
+                match result {
+                    Ok(Fetched::Downloaded) => println!("{} {}", entry.label, entry.source),
+                    Ok(Fetched::Cached) => println!("{} {} (cached)", entry.label, entry.source),
+                    Err(e) => {
+                        eprintln!("{} {}", entry.label, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Command::Generate => {
+            let mut failed = false;
+            for ((year, n), entry) in days {
+                // Each day draws from a stream of its own, so that its input
+                // does not depend on the other days selected.
+                let mut rng = Rng::new(args.seed).fork((year * 100 + n) as u64);
+                let mut generate = || entry.day.generate(&mut rng, args.size, &entry.params);
+                let result = match &entry.source {
+                    Source::File(path) if Path::new(path).exists() => {
+                        println!("{} {} (exists)", entry.label, entry.source);
+                        continue;
+                    }
+                    Source::File(path) => generate().and_then(|text| {
+                        if let Some(dir) = Path::new(path).parent() {
+                            fs::create_dir_all(dir)?;
+                        }
+                        fs::write(path, text)
+                            .map_err(|e| format!("cannot write {}: {}", path, e).into())
+                    }),
+                    Source::Stdin => generate().map(|text| print!("{}", text)),
+                    source => Err(format!("cannot generate into {}", source).into()),
+                };
+                match result {
+                    Ok(()) if entry.source != Source::Stdin => {
+                        println!("{} {}", entry.label, entry.source)
+                    }
+                    Ok(()) => {}
+                    Err(e) => {
+                        eprintln!("{} {}", entry.label, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Command::Crosscheck => {
+            let mut failed = false;
+            for (_, entry) in days {
+                if differential::references(entry.day, args.part).is_empty() {
+                    continue;
+                }
+                match differential::compare(
+                    entry.day,
+                    &entry.params,
+                    args.part,
+                    args.seed,
+                    args.cases,
+                    args.size,
+                ) {
+                    Ok(None) => println!("{} {} inputs agree", entry.label, args.cases),
+                    Ok(Some(found)) => {
+                        println!("{} {}", entry.label, found);
+                        failed = true;
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", entry.label, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
         }
-        let input: Box<dyn Fn() -> Box<dyn io::Read>> =
-            Box::new(|| Box::new(fs::File::open(&day.1).unwrap()));
-        println!("= {} =", day.0.tag());
-        if args.len() > 3 && args[3] == "1" {
-            day.0.part1(&input);
-        } else if args.len() > 3 && args[3] == "2" {
-            day.0.part2(&input);
-        } else {
-            day.0.part1(&input);
-            day.0.part2(&input);
+        Command::Run | Command::Bench | Command::Check => {
+            let bench = args.command == Command::Bench;
+            let answers = if args.command == Command::Check {
+                match args.answers_file().and_then(|path| Answers::load(&path)) {
+                    Ok(answers) => Some(answers),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        process::exit(1);
+                    }
+                }
+            } else {
+                None
+            };
+            let parts = [1, 2]
+                .into_iter()
+                .filter(|&part| args.part.is_none_or(|p| p == part))
+                .collect::<Vec<_>>();
+            let repeat = if bench { args.repeat } else { 1 };
+            let limit = match (args.timeout, config.get("timeout")) {
+                (None, Some(secs)) => Some(cli::timeout(secs).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                })),
+                (limit, _) => limit,
+            };
+            let days = days
+                .into_iter()
+                .rev()
+                .map(|(_, entry)| entry)
+                .collect::<Vec<_>>();
+            let mut failed = false;
+            let mut results = vec![];
+            if args.format == Format::Csv {
+                println!("{}", report::CSV_HEADER);
+            }
+            runner::run_days(&days, &parts, repeat, args.jobs, limit, |entry, result| {
+                if args.format != Format::Text {
+                    let result = result.map_err(|e| Answer::error(e.as_ref()));
+                    failed |= result.is_err();
+                    let records = match &result {
+                        Ok(result) => result
+                            .parts
+                            .iter()
+                            .map(|part| (part.part, part.answer.to_owned(), part.median()))
+                            .collect(),
+                        Err(error) => parts
+                            .iter()
+                            .map(|&part| (part, error.to_owned(), Duration::default()))
+                            .collect::<Vec<_>>(),
+                    };
+                    for (part, answer, duration) in records {
+                        let verdict = answers
+                            .as_ref()
+                            .map(|answers| answers.check(&entry.label, part, &answer));
+                        failed |= match &verdict {
+                            Some(verdict) => matches!(verdict, Verdict::Fail { .. }),
+                            None => matches!(answer, Answer::Error { .. }),
+                        };
+                        let record = Record {
+                            day: &entry.label,
+                            part,
+                            answer: &answer,
+                            duration,
+                            verdict: verdict.as_ref(),
+                        };
+                        if args.format == Format::Json {
+                            println!("{}", record.json());
+                        } else {
+                            println!("{}", record.csv());
+                        }
+                    }
+                    results.extend(result);
+                    return;
+                }
+                println!("= {} =", entry.label);
+                match result {
+                    Ok(result) => {
+                        for part in &result.parts {
+                            if let Some(answers) = &answers {
+                                let verdict = answers.check(&entry.label, part.part, &part.answer);
+                                failed |= matches!(verdict, Verdict::Fail { .. });
+                                println!("{} {}", part.part, verdict);
+                            } else if let Answer::Error { message, causes } = &part.answer {
+                                failed = true;
+                                eprintln!("part {}: error: {}", part.part, message);
+                                for cause in causes {
+                                    eprintln!("  caused by: {}", cause);
+                                }
+                            } else {
+                                println!("{}", part.answer);
+                            }
+                        }
+                        results.push(result);
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        failed = true;
+                    }
+                }
+            });
+            if args.format == Format::Text {
+                if bench {
+                    print!("\n{}", runner::bench_table(&results));
+                } else if args.time {
+                    print!("\n{}", runner::timing_table(&results));
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
         }
     }
 }
@@ -0,0 +1,310 @@
+use crate::day::*;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// A template for lines of input, such as
+/// `"Sensor at x={int}, y={int}: closest beacon is at x={int}, y={int}"`,
+/// matched against a line to extract its fields.  In the template
+///
+/// - `{int}` is a field of digits, optionally signed,
+/// - `{word}` is a field of letters, digits and underscores,
+/// - `{}` is a field of any text, as little as it takes for the rest of the
+///   template to match,
+/// - `{a|b}` is one of the given texts, which is not a field,
+/// - a run of whitespace matches any non-empty run of whitespace,
+/// - `{{` and `}}` are literal braces, and anything else is literal text.
+///
+/// A malformed template is a bug, and panics when used.
+#[derive(Clone, Copy, Debug)]
+pub struct Pattern {
+    template: &'static str,
+}
+
+#[derive(Debug)]
+enum Piece<'t> {
+    Literal(String),
+    Space,
+    Choice(Vec<&'t str>),
+    Int,
+    Word,
+    Any,
+}
+
+/// The fields of a matched line, taken in order and parsed to the type
+/// wanted, with errors pointing at the column of the offending field.
+#[derive(Debug)]
+pub struct Fields<'a> {
+    line: &'a str,
+    fields: VecDeque<(usize, &'a str)>,
+}
+
+impl Pattern {
+    pub const fn new(template: &'static str) -> Self {
+        Self { template }
+    }
+
+    fn pieces(&self) -> Vec<Piece<'static>> {
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = self.template.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let piece = match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => {
+                    literal.push(c);
+                    continue;
+                }
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => {
+                    literal.push(c);
+                    continue;
+                }
+                '{' => {
+                    let end = self.template[i..]
+                        .find('}')
+                        .unwrap_or_else(|| panic!("unclosed field in {:?}", self.template));
+                    while chars.next_if(|&(j, _)| j <= i + end).is_some() {}
+                    match &self.template[i + 1..i + end] {
+                        "int" => Piece::Int,
+                        "word" => Piece::Word,
+                        "" => Piece::Any,
+                        choice if choice.contains('|') => {
+                            Piece::Choice(choice.split('|').collect())
+                        }
+                        field => panic!("unknown field {:?} in {:?}", field, self.template),
+                    }
+                }
+                '}' => panic!("unopened field in {:?}", self.template),
+                c if c.is_whitespace() => {
+                    while chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+                    Piece::Space
+                }
+                c => {
+                    literal.push(c);
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                pieces.push(Piece::Literal(literal.split_off(0)));
+            }
+            pieces.push(piece);
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        pieces
+    }
+
+    /// Match all of `line`, or fail with the column where it diverged from
+    /// the template, as far as any attempt to match it got.
+    pub fn fields<'a>(&self, line: &'a str) -> BoxResult<Fields<'a>> {
+        let mut fields = vec![];
+        let mut furthest = 0;
+        if Self::matches(&self.pieces(), line, 0, &mut fields, &mut furthest) {
+            return Ok(Fields {
+                line,
+                fields: fields.into(),
+            });
+        }
+        let column = line[..furthest].chars().count() + 1;
+        Err(if furthest < line.len() {
+            AocError::new(Reason::Parse)
+                .column(column)
+                .token(&line[furthest..])
+        } else {
+            AocError::new(Reason::MissingField).column(column)
+        })?
+    }
+
+    fn matches<'a>(
+        pieces: &[Piece],
+        line: &'a str,
+        pos: usize,
+        fields: &mut Vec<(usize, &'a str)>,
+        furthest: &mut usize,
+    ) -> bool {
+        *furthest = (*furthest).max(pos);
+        let Some((piece, rest)) = pieces.split_first() else {
+            return pos == line.len();
+        };
+        let tail = &line[pos..];
+        let span = |n: usize| (n > 0).then_some(n);
+        let end = match piece {
+            Piece::Literal(s) => tail.starts_with(s.as_str()).then_some(s.len()),
+            Piece::Space => span(tail.len() - tail.trim_start().len()),
+            Piece::Choice(choices) => {
+                return choices.iter().any(|choice| {
+                    tail.starts_with(choice)
+                        && Self::matches(rest, line, pos + choice.len(), fields, furthest)
+                })
+            }
+            Piece::Int => {
+                let sign = tail.starts_with(['-', '+']) as usize;
+                let digits = tail[sign..].len()
+                    - tail[sign..]
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .len();
+                span(digits).map(|digits| sign + digits)
+            }
+            Piece::Word => span(
+                tail.len()
+                    - tail
+                        .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_')
+                        .len(),
+            ),
+            Piece::Any => {
+                // As little as possible, so try the shortest field first.
+                return tail
+                    .char_indices()
+                    .skip(1)
+                    .map(|(i, _)| i)
+                    .chain([tail.len()])
+                    .any(|end| {
+                        fields.push((pos, &tail[..end]));
+                        Self::matches(rest, line, pos + end, fields, furthest) || {
+                            fields.pop();
+                            false
+                        }
+                    });
+            }
+        };
+        let Some(end) = end else {
+            return false;
+        };
+        let is_field = matches!(piece, Piece::Int | Piece::Word);
+        if is_field {
+            fields.push((pos, &tail[..end]));
+        }
+        Self::matches(rest, line, pos + end, fields, furthest) || {
+            if is_field {
+                fields.pop();
+            }
+            false
+        }
+    }
+}
+
+impl<'a> Fields<'a> {
+    fn column(&self, pos: usize) -> usize {
+        self.line[..pos].chars().count() + 1
+    }
+
+    /// Parse the next field.
+    pub fn field<T>(&mut self) -> BoxResult<T>
+    where
+        T: FromStr,
+        T::Err: Into<Box<dyn error::Error>>,
+    {
+        let (pos, field) = self
+            .fields
+            .pop_front()
+            .with_reason(Reason::MissingField)
+            .at_column(self.column(self.line.len()))?;
+        field
+            .parse()
+            .with_reason(Reason::Parse)
+            .at_column(self.column(pos))
+            .with_token(field)
+    }
+
+    /// Parse the next field as a list of items separated by `separator`.
+    pub fn list<T>(&mut self, separator: &str) -> BoxResult<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: Into<Box<dyn error::Error>>,
+    {
+        let (pos, field) = self
+            .fields
+            .pop_front()
+            .with_reason(Reason::MissingField)
+            .at_column(self.column(self.line.len()))?;
+        let mut offset = pos;
+        field
+            .split(separator)
+            .map(|item| {
+                let column = self.column(offset);
+                offset += item.len() + separator.len();
+                item.parse()
+                    .with_reason(Reason::Parse)
+                    .at_column(column)
+                    .with_token(item)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENSOR: Pattern =
+        Pattern::new("Sensor at x={int}, y={int}: closest beacon is at x={int}, y={int}");
+    const VALVE: Pattern = Pattern::new(
+        "Valve {word} has flow rate={int}; {tunnels lead|tunnel leads} to {valves|valve} {}",
+    );
+
+    #[test]
+    fn fields() {
+        let mut fields = SENSOR
+            .fields("Sensor at x=2, y=18: closest beacon is at x=-2, y=15")
+            .unwrap();
+        assert_eq!(fields.field::<i64>().unwrap(), 2);
+        assert_eq!(fields.field::<i64>().unwrap(), 18);
+        assert_eq!(fields.field::<i64>().unwrap(), -2);
+        assert_eq!(fields.field::<i64>().unwrap(), 15);
+        assert!(fields.field::<i64>().is_err());
+
+        let mut fields = VALVE
+            .fields("Valve AA has flow rate=0;  tunnels lead to valves DD, II, BB")
+            .unwrap();
+        assert_eq!(fields.field::<String>().unwrap(), "AA");
+        assert_eq!(fields.field::<usize>().unwrap(), 0);
+        assert_eq!(fields.list::<String>(", ").unwrap(), vec!["DD", "II", "BB"]);
+        let mut fields = VALVE
+            .fields("Valve HH has flow rate=22; tunnel leads to valve GG")
+            .unwrap();
+        assert_eq!(fields.field::<String>().unwrap(), "HH");
+        assert_eq!(fields.field::<usize>().unwrap(), 22);
+        assert_eq!(fields.list::<String>(", ").unwrap(), vec!["GG"]);
+
+        let pattern = Pattern::new("{{{word}}} is {} and {}");
+        let mut fields = pattern.fields("{x} is a and b and c").unwrap();
+        assert_eq!(fields.field::<String>().unwrap(), "x");
+        assert_eq!(fields.field::<String>().unwrap(), "a");
+        assert_eq!(fields.field::<String>().unwrap(), "b and c");
+    }
+
+    #[test]
+    fn divergence() {
+        let e = |pattern: Pattern, line| pattern.fields(line).unwrap_err().to_string();
+        assert_eq!(
+            e(
+                SENSOR,
+                "Sensor at x=2, y=18: nearest beacon is at x=-2, y=15"
+            ),
+            "parse failure at column 22 near \"nearest beacon is at x=-2, y=15\""
+        );
+        assert_eq!(
+            e(SENSOR, "Sensor at x=2, y=: closest beacon is at x=-2, y=15"),
+            "parse failure at column 18 near \": closest beacon is at x=-2, y=15\""
+        );
+        assert_eq!(
+            e(SENSOR, "Sensor at x=2, y=18: closest beacon is at x=-2,"),
+            "missing field at column 48"
+        );
+        assert_eq!(
+            e(
+                VALVE,
+                "Valve AA has flow rate=0; tunnels leads to valves DD"
+            ),
+            "parse failure at column 39 near \"s to valves DD\""
+        );
+        let mut fields = VALVE
+            .fields("Valve AA has flow rate=0; tunnels lead to valves DD, I!")
+            .unwrap();
+        fields.field::<String>().unwrap();
+        let e = fields
+            .field::<u8>()
+            .and(fields.list::<u8>(", "))
+            .unwrap_err();
+        assert_eq!(e.to_string(), "parse failure at column 50 near \"DD\"");
+    }
+}
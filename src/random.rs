@@ -0,0 +1,90 @@
+use num::PrimInt;
+use std::ops::RangeInclusive;
+
+/// A small pseudo-random number generator, SplitMix64, whose numbers for a
+/// given seed never change, so that a seed names the same generated input on
+/// every machine and in every version.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// An independent generator for the given stream, e.g. one per day, so
+    /// that what one stream draws does not change the numbers of another.
+    pub fn fork(&self, stream: u64) -> Self {
+        Self::new(self.state ^ mix(stream.wrapping_add(GOLDEN_GAMMA)))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    /// A number in the range, which must not be empty.
+    pub fn range<T: PrimInt>(&mut self, range: RangeInclusive<T>) -> T {
+        let (start, end) = (range.start().to_i128(), range.end().to_i128());
+        let (Some(start), Some(end)) = (start, end) else {
+            panic!("range too wide");
+        };
+        assert!(start <= end, "empty range");
+        let span = (end - start + 1) as u128;
+        T::from(start + (self.next_u64() as u128 % span) as i128).expect("in range")
+    }
+
+    /// Whether an event of the given probability happens.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// An item of the slice, which must not be empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..=items.len() - 1)]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..=i));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let mut rng = Rng::new(42);
+        let numbers = (0..100).map(|_| rng.range(-3..=3)).collect::<Vec<i64>>();
+        assert!(numbers.iter().all(|n| (-3..=3).contains(n)));
+        assert!((-3..=3).all(|n| numbers.contains(&n)));
+        let mut again = Rng::new(42);
+        assert!(numbers.iter().all(|&n| again.range(-3..=3) == n));
+        assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+
+        let (a, b) = (rng.fork(1).next_u64(), rng.fork(2).next_u64());
+        assert_ne!(a, b);
+        assert_eq!(rng.fork(1).next_u64(), a);
+
+        let mut items = (0..10).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..10).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+        assert_eq!(rng.range(usize::MAX..=usize::MAX), usize::MAX);
+        assert!(!rng.chance(0.0) && rng.chance(1.0));
+    }
+}
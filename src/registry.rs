@@ -0,0 +1,34 @@
+use crate::day::*;
+use std::collections::BTreeMap;
+
+/// The solved days of all years, keyed by (year, day).
+#[derive(Default)]
+pub struct Registry {
+    days: BTreeMap<(usize, usize), Box<dyn Day>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a day of the given year, numbered by its tag.
+    pub fn add(mut self, year: usize, day: Box<dyn Day>) -> Self {
+        let n = day.tag().parse().expect("day tags are numbers");
+        self.days.insert((year, n), day);
+        self
+    }
+
+    pub fn get(&self, year: usize, day: usize) -> Option<&dyn Day> {
+        self.days.get(&(year, day)).map(|day| day.as_ref())
+    }
+
+    /// All the days, ordered by year and day.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &dyn Day)> {
+        self.days.iter().map(|(&key, day)| (key, day.as_ref()))
+    }
+
+    pub fn years(&self) -> Vec<usize> {
+        self.days.keys().map(|&(year, _)| year).dedup().collect()
+    }
+}
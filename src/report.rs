@@ -0,0 +1,169 @@
+use crate::answers::Verdict;
+use crate::day::*;
+use simple_error::SimpleError;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How results are printed: as text for people, or as one JSON object per
+/// line or CSV row per day and part for other tools.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(SimpleError::new(format!("invalid format \"{}\"", s))),
+        }
+    }
+}
+
+pub const CSV_HEADER: &str = "day,part,type,answer,duration,error,verdict";
+
+/// The result of one part of a day.  The duration is the median of the
+/// solves, and the verdict is only given when checking answers.
+pub struct Record<'a> {
+    pub day: &'a str,
+    pub part: usize,
+    pub answer: &'a Answer,
+    pub duration: Duration,
+    pub verdict: Option<&'a Verdict>,
+}
+
+impl Record<'_> {
+    fn fields(&self) -> (String, String) {
+        match self.answer {
+            Answer::Error { message, causes } => (
+                String::new(),
+                [message.to_owned()]
+                    .into_iter()
+                    .chain(causes.iter().cloned())
+                    .join(": "),
+            ),
+            Answer::Unimplemented => (String::new(), String::new()),
+            answer => (answer.lines().join("\n"), String::new()),
+        }
+    }
+
+    fn verdict(&self) -> Option<&'static str> {
+        self.verdict.map(|verdict| match verdict {
+            Verdict::Pass => "pass",
+            Verdict::Fail { .. } => "fail",
+            Verdict::Missing => "missing",
+        })
+    }
+
+    pub fn json(&self) -> String {
+        let (answer, error) = self.fields();
+        let answer = match self.answer {
+            Answer::Integer(n) => n.to_string(),
+            Answer::Text(_) | Answer::Grid(_) => json_string(&answer),
+            _ => "null".to_owned(),
+        };
+        let error = match self.answer {
+            Answer::Error { .. } => json_string(&error),
+            _ => "null".to_owned(),
+        };
+        format!(
+            "{{\"day\":{},\"part\":{},\"type\":\"{}\",\"answer\":{},\"duration\":{},\"error\":{},\"verdict\":{}}}",
+            json_string(self.day),
+            self.part,
+            self.answer.kind(),
+            answer,
+            self.duration.as_secs_f64(),
+            error,
+            self.verdict().map_or("null".to_owned(), json_string)
+        )
+    }
+
+    pub fn csv(&self) -> String {
+        let (answer, error) = self.fields();
+        [
+            csv_field(self.day),
+            self.part.to_string(),
+            self.answer.kind().to_owned(),
+            csv_field(&answer),
+            self.duration.as_secs_f64().to_string(),
+            csv_field(&error),
+            self.verdict().unwrap_or_default().to_owned(),
+        ]
+        .join(",")
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut rv = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => rv.push_str("\\\""),
+            '\\' => rv.push_str("\\\\"),
+            '\n' => rv.push_str("\\n"),
+            '\r' => rv.push_str("\\r"),
+            '\t' => rv.push_str("\\t"),
+            c if c.is_control() => write!(rv, "\\u{:04x}", c as u32).unwrap(),
+            c => rv.push(c),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let record = |answer, verdict| Record {
+            day: "10",
+            part: 2,
+            answer,
+            duration: Duration::from_millis(1500),
+            verdict,
+        };
+        let grid = Answer::Grid(vec!["#.".to_owned(), ".#".to_owned()]);
+        assert_eq!(
+            record(&grid, None).json(),
+            r##"{"day":"10","part":2,"type":"grid","answer":"#.\n.#","duration":1.5,"error":null,"verdict":null}"##
+        );
+        assert_eq!(
+            record(&grid, Some(&Verdict::Pass)).csv(),
+            "10,2,grid,\"#.\n.#\",1.5,,pass"
+        );
+        let error = Answer::Error {
+            message: "parse failure near \"Q\"".to_owned(),
+            causes: vec!["invalid digit".to_owned()],
+        };
+        assert_eq!(
+            record(&error, None).json(),
+            r#"{"day":"10","part":2,"type":"error","answer":null,"duration":1.5,"error":"parse failure near \"Q\": invalid digit","verdict":null}"#
+        );
+        assert_eq!(
+            record(&error, None).csv(),
+            "10,2,error,,1.5,\"parse failure near \"\"Q\"\": invalid digit\","
+        );
+        assert_eq!(
+            record(&Answer::Integer(-3), None).json(),
+            r#"{"day":"10","part":2,"type":"integer","answer":-3,"duration":1.5,"error":null,"verdict":null}"#
+        );
+        assert!("yaml".parse::<Format>().is_err());
+    }
+}
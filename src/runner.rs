@@ -0,0 +1,602 @@
+use crate::cancel;
+use crate::day::*;
+use crate::input::{self, Source};
+use crate::timing;
+use crate::visual;
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The answer of a part, how long each solve of it took, and how much of
+/// that each spent parsing the input.
+pub struct PartResult {
+    pub part: usize,
+    pub answer: Answer,
+    pub durations: Vec<Duration>,
+    pub parsing: Vec<Duration>,
+}
+
+impl PartResult {
+    pub fn min(&self) -> Duration {
+        self.durations.iter().copied().min().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.durations.iter().copied().max().unwrap_or_default()
+    }
+
+    pub fn median(&self) -> Duration {
+        median(&self.durations)
+    }
+
+    /// How long each solve spent on anything but parsing the input.
+    pub fn solving(&self) -> Vec<Duration> {
+        self.durations
+            .iter()
+            .enumerate()
+            .map(|(i, d)| d.saturating_sub(self.parsing.get(i).copied().unwrap_or_default()))
+            .collect()
+    }
+}
+
+pub fn median(durations: &[Duration]) -> Duration {
+    let sorted = durations.iter().copied().sorted().collect::<Vec<_>>();
+    match sorted.len() {
+        0 => Duration::default(),
+        n if n % 2 == 0 => (sorted[n / 2 - 1] + sorted[n / 2]) / 2,
+        n => sorted[n / 2],
+    }
+}
+
+/// A day to solve: its label in reports, e.g. "17" or "2020:08", its solver,
+/// where its input is read from, its parameters, how verbosely it traces and
+/// how the frames of its simulations are shown, if at all.
+#[derive(Clone)]
+pub struct Entry<'a> {
+    pub label: String,
+    pub day: &'a dyn Day,
+    pub source: Source,
+    pub params: Params,
+    pub trace: u8,
+    pub visualize: Option<visual::Mode>,
+}
+
+/// The results of a day's parts, and how long reading its input into memory
+/// took, unless it was read as it was solved.
+pub struct DayResult {
+    pub tag: String,
+    pub read: Option<Duration>,
+    pub parts: Vec<PartResult>,
+}
+
+/// The input of a day as its parts get it: read from its source as they go,
+/// or read into memory beforehand.
+#[derive(Clone)]
+enum Input {
+    Streamed(Source),
+    Buffered(input::Shared),
+}
+
+impl Input {
+    /// The input factory of the parts, reading of which counts as parsing.
+    fn factory(&self) -> Box<dyn Fn() -> Box<dyn io::Read> + '_> {
+        let open: Box<dyn Fn() -> Box<dyn io::Read>> = match self {
+            Self::Streamed(source) => Box::new(input::streamed(source)),
+            Self::Buffered(data) => Box::new(input::shared(data)),
+        };
+        Box::new(move || Box::new(timing::Reader(open())))
+    }
+}
+
+/// The answer of a part that failed for `reason`, with `cause` as its cause.
+fn failure(label: &str, reason: Reason, cause: String) -> Answer {
+    Answer::error(&AocError {
+        source: Some(cause.into()),
+        ..AocError::new(reason).day(label)
+    })
+}
+
+thread_local! {
+    /// Whether a panic on this thread is caught and its message reported as
+    /// the answer of the part that panicked.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The answer of `f`, solving a part of the day labelled `label`, or the
+/// failure it panicked with.  The message of such a panic is reported in the
+/// answer only, rather than printed with a backtrace as well.
+pub(crate) fn catching<F: FnOnce() -> Answer>(label: &str, f: F) -> Answer {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.get() {
+                hook(info)
+            }
+        }))
+    });
+    let catching = CATCHING.replace(true);
+    let answer = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.set(catching);
+    answer.unwrap_or_else(|payload| panicked(label, payload.as_ref()))
+}
+
+/// The answer of a part that panicked, caused by the panic's message.
+fn panicked(label: &str, payload: &(dyn Any + Send)) -> Answer {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "no message".to_owned());
+    failure(label, Reason::Panic, message)
+}
+
+fn solve(entry: &Entry, input: &Input, part: usize, repeat: usize) -> PartResult {
+    let params = entry.params.for_part(part);
+    let input = input.factory();
+    let visualize = entry.visualize.as_ref();
+    let (answers, durations, parsing): (Vec<_>, Vec<_>, Vec<_>) =
+        trace::with_level(&entry.label, entry.trace, || {
+            visual::with_mode(&entry.label, part, visualize, || {
+                (0..repeat.max(1))
+                    .take_while(|_| !cancel::cancelled())
+                    .map(|_| {
+                        let start = Instant::now();
+                        let (answer, parsing) = timing::timed(|| {
+                            catching(&entry.label, || {
+                                if part == 1 {
+                                    entry.day.part1(&input, &params)
+                                } else {
+                                    entry.day.part2(&input, &params)
+                                }
+                            })
+                        });
+                        (answer, start.elapsed(), parsing)
+                    })
+                    .multiunzip()
+            })
+        });
+    PartResult {
+        part,
+        answer: answers.into_iter().next().unwrap_or(Answer::Unimplemented),
+        durations,
+        parsing,
+    }
+}
+
+/// Solve a part as `solve` does, but when `limit` is given, on a thread of
+/// its own, which is given up on when the part takes longer than that per
+/// solve.  The part is then reported as timed out and cancelled, which
+/// stops it at its next check for cancellation, while the other parts are
+/// solved.  A part that never checks cannot be stopped, and its thread is
+/// left running until it is done.
+fn isolate(
+    entry: &Entry<'static>,
+    input: &Input,
+    part: usize,
+    repeat: usize,
+    limit: Option<Duration>,
+) -> PartResult {
+    let Some(limit) = limit else {
+        return solve(entry, input, part, repeat);
+    };
+    let (tx, rx) = mpsc::channel();
+    let (isolated, input) = (entry.to_owned(), input.to_owned());
+    let token = cancel::Token::new();
+    let cancellable = token.to_owned();
+    thread::spawn(move || {
+        cancel::with_token(&cancellable, || {
+            tx.send(solve(&isolated, &input, part, repeat))
+        })
+    });
+    let start = Instant::now();
+    let received = rx.recv_timeout(limit.saturating_mul(repeat.max(1) as u32));
+    received.unwrap_or_else(|e| {
+        token.cancel();
+        PartResult {
+            part,
+            answer: match e {
+                RecvTimeoutError::Timeout => failure(
+                    &entry.label,
+                    Reason::TimedOut,
+                    format!("no answer after {}", format_duration(limit)),
+                ),
+                RecvTimeoutError::Disconnected => {
+                    failure(&entry.label, Reason::Panic, "no answer".to_owned())
+                }
+            },
+            durations: vec![start.elapsed()],
+            parsing: vec![],
+        }
+    })
+}
+
+/// Solve the given parts of the days, each `repeat` times, on `threads`
+/// worker threads, timing every solve of a part, and how much of it is spent
+/// parsing the input.  Input files are read as they are parsed, opened
+/// afresh by each part, so that they need not fit in memory, unless the
+/// parts are solved more than once: they are then read into memory up
+/// front, and timed separately, so that disk access is not part of the
+/// parse timings.  A part that panics, or takes longer than `limit` per
+/// solve, is reported as failed, without holding up the rest.
+/// Results are passed to `report` in the order of `days`, each as soon as it
+/// and all days before it are done.
+pub fn run_days<F>(
+    days: &[Entry<'static>],
+    parts: &[usize],
+    repeat: usize,
+    threads: usize,
+    limit: Option<Duration>,
+    mut report: F,
+) where
+    F: FnMut(&Entry, BoxResult<DayResult>),
+{
+    let inputs = days
+        .iter()
+        .map(|entry| {
+            let start = Instant::now();
+            match &entry.source {
+                source @ Source::File(_) if repeat <= 1 => source
+                    .open()
+                    .map(|_| (Input::Streamed(source.to_owned()), None))
+                    .map_err(|e| format!("cannot open {}: {}", source, e)),
+                source => source
+                    .read()
+                    .map(|data| {
+                        let input = Input::Buffered(input::Shared::new(data));
+                        (input, Some(start.elapsed()))
+                    })
+                    .map_err(|e| e.to_string()),
+            }
+        })
+        .collect::<Vec<_>>();
+    let jobs = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| input.is_ok())
+        .flat_map(|(i, _)| (0..parts.len()).map(move |j| (i, j)))
+        .collect::<Vec<_>>();
+    let mut done = inputs
+        .iter()
+        .map(|_| parts.iter().map(|_| None).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let next_job = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            let tx = tx.clone();
+            let (days, inputs, jobs, next_job) = (days, &inputs, &jobs, &next_job);
+            scope.spawn(move || {
+                while let Some(&(i, j)) = jobs.get(next_job.fetch_add(1, Ordering::Relaxed)) {
+                    if let Ok((data, _)) = &inputs[i] {
+                        let result = isolate(&days[i], data, parts[j], repeat, limit);
+                        if tx.send((i, j, result)).is_err() {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+        drop(tx);
+        let mut next_day = 0;
+        let mut flush = |done: &mut Vec<Vec<Option<PartResult>>>| {
+            while next_day < days.len()
+                && (inputs[next_day].is_err() || done[next_day].iter().all(|r| r.is_some()))
+            {
+                let entry = &days[next_day];
+                report(
+                    entry,
+                    match &inputs[next_day] {
+                        Ok((_, read)) => Ok(DayResult {
+                            tag: entry.label.to_owned(),
+                            read: read.to_owned(),
+                            parts: done[next_day].iter_mut().flat_map(|r| r.take()).collect(),
+                        }),
+                        Err(e) => Err(e.to_owned().into()),
+                    },
+                );
+                next_day += 1;
+            }
+        };
+        flush(&mut done);
+        for (i, j, result) in rx {
+            done[i][j] = Some(result);
+            flush(&mut done);
+        }
+    });
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs >= 1.0 {
+        format!("{:.3} s", secs)
+    } else if secs >= 1e-3 {
+        format!("{:.3} ms", secs * 1e3)
+    } else {
+        format!("{:.3} µs", secs * 1e6)
+    }
+}
+
+/// A table with the (median) time each part spent parsing its input, and
+/// solving it.
+pub fn timing_table(results: &[DayResult]) -> String {
+    let mut s = format!(
+        "{:>3} {:>12} {:>12} {:>12} {:>12}\n",
+        "Day", "Parse 1", "Solve 1", "Parse 2", "Solve 2"
+    );
+    for result in results {
+        let part = |n| {
+            result
+                .parts
+                .iter()
+                .find(|p| p.part == n)
+                .map_or(["-".to_owned(), "-".to_owned()], |p| {
+                    [median(&p.parsing), median(&p.solving())].map(format_duration)
+                })
+        };
+        let ([parse1, solve1], [parse2, solve2]) = (part(1), part(2));
+        writeln!(
+            s,
+            "{:>3} {:>12} {:>12} {:>12} {:>12}",
+            result.tag, parse1, solve1, parse2, solve2
+        )
+        .unwrap();
+    }
+    s
+}
+
+/// A table with the time taken to read each input into memory, and of each
+/// part the median time spent parsing its input, and the minimum, median and
+/// maximum time spent solving it.
+pub fn bench_table(results: &[DayResult]) -> String {
+    let mut s = format!(
+        "{:>3} {:>12} {:>4} {:>6} {:>12} {:>12} {:>12} {:>12}\n",
+        "Day", "Read", "Part", "Runs", "Parse", "Solve min", "Solve median", "Solve max"
+    );
+    for result in results {
+        for (i, part) in result.parts.iter().enumerate() {
+            let read = match result.read {
+                Some(read) if i == 0 => format_duration(read),
+                _ => String::new(),
+            };
+            let solving = part.solving();
+            writeln!(
+                s,
+                "{:>3} {:>12} {:>4} {:>6} {:>12} {:>12} {:>12} {:>12}",
+                result.tag,
+                read,
+                part.part,
+                part.durations.len(),
+                format_duration(median(&part.parsing)),
+                format_duration(solving.iter().copied().min().unwrap_or_default()),
+                format_duration(median(&solving)),
+                format_duration(solving.iter().copied().max().unwrap_or_default())
+            )
+            .unwrap();
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Read;
+    use std::sync::atomic::AtomicBool;
+
+    struct Length(&'static str);
+
+    impl Day for Length {
+        fn tag(&self) -> &str {
+            self.0
+        }
+
+        fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+            Ok(io::BufReader::new(input()).bytes().count()).into()
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let dir = std::env::temp_dir().join(format!("aoc-runner-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        static DAYS: [Length; 8] = [
+            Length("01"),
+            Length("02"),
+            Length("03"),
+            Length("04"),
+            Length("05"),
+            Length("06"),
+            Length("07"),
+            Length("08"),
+        ];
+        let days = DAYS
+            .iter()
+            .map(|day| {
+                let path = dir.join(day.tag());
+                if day.tag() != "05" {
+                    fs::write(&path, "x".repeat(day.tag().parse().unwrap())).unwrap();
+                }
+                Entry {
+                    label: day.tag().to_owned(),
+                    day,
+                    source: Source::File(path.to_string_lossy().into_owned()),
+                    params: Params::new(),
+                    trace: 0,
+                    visualize: None,
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut reported = vec![];
+        run_days(&days, &[1, 2], 1, 4, None, |entry, result| {
+            reported.push((
+                entry.label.to_owned(),
+                result
+                    .map(|r| r.parts.into_iter().map(|p| p.answer).collect::<Vec<_>>())
+                    .ok(),
+            ))
+        });
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reported.len(), 8);
+        for (i, (tag, answers)) in reported.into_iter().enumerate() {
+            assert_eq!(tag, days[i].label);
+            if tag == "05" {
+                assert_eq!(answers, None);
+            } else {
+                assert_eq!(
+                    answers,
+                    Some(vec![Answer::Integer(i as i64 + 1), Answer::Unimplemented])
+                );
+            }
+        }
+    }
+
+    struct Faulty;
+
+    // Whether Faulty's part 2 noticed it was cancelled, and stopped.
+    static STOPPED: AtomicBool = AtomicBool::new(false);
+
+    impl Day for Faulty {
+        fn tag(&self) -> &str {
+            "05"
+        }
+
+        fn part1(&self, _input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+            panic!("XXX panics")
+        }
+
+        fn part2(&self, _input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+            while !cancel::cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            STOPPED.store(true, Ordering::Relaxed);
+            Answer::Unimplemented
+        }
+    }
+
+    #[test]
+    fn isolation() {
+        static FAULTY: Faulty = Faulty;
+        static LENGTH: Length = Length("06");
+        let days = [&FAULTY as &dyn Day, &LENGTH].map(|day| Entry {
+            label: day.tag().to_owned(),
+            day,
+            source: Source::Inline("input".to_owned()),
+            params: Params::new(),
+            trace: 0,
+            visualize: None,
+        });
+        let mut reported = vec![];
+        let limit = Some(Duration::from_millis(100));
+        run_days(&days, &[1, 2], 1, 1, limit, |_, result| {
+            reported.extend(result.unwrap().parts.into_iter().map(|p| p.answer))
+        });
+        let error = |message: &str, cause: &str| Answer::Error {
+            message: message.to_owned(),
+            causes: vec![cause.to_owned()],
+        };
+        assert_eq!(
+            reported,
+            vec![
+                error("panicked in day 05", "XXX panics"),
+                error("timed out in day 05", "no answer after 100.000 ms"),
+                Answer::Integer(5),
+                Answer::Unimplemented,
+            ]
+        );
+        let start = Instant::now();
+        while !STOPPED.load(Ordering::Relaxed) && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(STOPPED.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn unlimited() {
+        struct Here;
+
+        impl Day for Here {
+            fn tag(&self) -> &str {
+                "01"
+            }
+
+            fn part1(&self, _input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+                Answer::Text(format!("{:?}", thread::current().id()))
+            }
+        }
+
+        static HERE: Here = Here;
+        let entry = Entry {
+            label: "01".to_owned(),
+            day: &HERE,
+            source: Source::Inline("input".to_owned()),
+            params: Params::new(),
+            trace: 0,
+            visualize: None,
+        };
+        let input = Input::Buffered(input::Shared::new(b"input".to_vec()));
+        let here = Answer::Text(format!("{:?}", thread::current().id()));
+        assert_eq!(isolate(&entry, &input, 1, 1, None).answer, here);
+        assert_ne!(
+            isolate(&entry, &input, 1, 1, Some(Duration::MAX)).answer,
+            here
+        );
+        let answer = catching("01", || panic!("XXX panics"));
+        assert_eq!(answer.to_string(), "error: panicked in day 01: XXX panics");
+        assert!(!CATCHING.get());
+    }
+
+    #[test]
+    fn statistics() {
+        let part = PartResult {
+            part: 1,
+            answer: Answer::Integer(0),
+            durations: [4, 1, 3, 2].map(Duration::from_millis).to_vec(),
+            parsing: [1, 1, 2].map(Duration::from_millis).to_vec(),
+        };
+        assert_eq!(part.min(), Duration::from_millis(1));
+        assert_eq!(part.median(), Duration::from_micros(2500));
+        assert_eq!(part.max(), Duration::from_millis(4));
+        assert_eq!(
+            part.solving(),
+            [3, 0, 1, 2].map(Duration::from_millis).to_vec()
+        );
+        assert_eq!(median(&part.parsing), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn tables() {
+        let part = |part, millis, parsing| PartResult {
+            part,
+            answer: Answer::Integer(0),
+            durations: vec![Duration::from_millis(millis)],
+            parsing: vec![Duration::from_millis(parsing)],
+        };
+        let results = [DayResult {
+            tag: "06".to_owned(),
+            read: Some(Duration::from_millis(3)),
+            parts: vec![part(1, 5, 1), part(2, 2, 0)],
+        }];
+        assert_eq!(
+            timing_table(&results).lines().collect::<Vec<_>>(),
+            [
+                "Day      Parse 1      Solve 1      Parse 2      Solve 2",
+                " 06     1.000 ms     4.000 ms     0.000 µs     2.000 ms",
+            ]
+        );
+        assert_eq!(
+            bench_table(&results).lines().skip(1).collect::<Vec<_>>(),
+            [
+                " 06     3.000 ms    1      1     1.000 ms     4.000 ms     4.000 ms     4.000 ms",
+                " 06                 2      1     0.000 µs     2.000 ms     2.000 ms     2.000 ms",
+            ]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::cancel;
+use num::Zero;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// The cheapest way found to a goal: its cost and the states along it, the
+/// start and the goal included.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+/// The states seen so far, numbered in the order they were found, with the
+/// state each was reached from.
+struct Nodes<S> {
+    states: Vec<S>,
+    index: HashMap<S, usize>,
+    parents: Vec<Option<usize>>,
+}
+
+impl<S: Clone + Eq + Hash> Nodes<S> {
+    fn new(start: S) -> Self {
+        Self {
+            states: vec![start.to_owned()],
+            index: HashMap::from([(start, 0)]),
+            parents: vec![None],
+        }
+    }
+
+    /// The number of a state, and whether it is new.
+    fn insert(&mut self, state: S, parent: usize) -> (usize, bool) {
+        if let Some(&i) = self.index.get(&state) {
+            return (i, false);
+        }
+        let i = self.states.len();
+        self.states.push(state.to_owned());
+        self.index.insert(state, i);
+        self.parents.push(Some(parent));
+        (i, true)
+    }
+
+    fn path<C>(&self, cost: C, mut i: usize) -> Path<S, C> {
+        let mut states = vec![self.states[i].to_owned()];
+        while let Some(parent) = self.parents[i] {
+            states.push(self.states[parent].to_owned());
+            i = parent;
+        }
+        states.reverse();
+        Path { cost, states }
+    }
+}
+
+/// Breadth-first search from `start` for the fewest steps to a state
+/// satisfying `goal`.  Like the other searches, it stops as if it had run
+/// out of states when the part it is solving is cancelled.
+pub fn bfs<S, I, N, G>(start: S, mut successors: N, mut goal: G) -> Option<Path<S, usize>>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    G: FnMut(&S) -> bool,
+{
+    let mut nodes = Nodes::new(start);
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some((i, steps)) = queue.pop_front() {
+        if cancel::cancelled() {
+            break;
+        }
+        if goal(&nodes.states[i]) {
+            return Some(nodes.path(steps, i));
+        }
+        for next in successors(&nodes.states[i]) {
+            if let (j, true) = nodes.insert(next, i) {
+                queue.push_back((j, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+/// All states reachable from `start`, with the fewest steps to each.
+pub fn reachable<S, I, N>(start: S, mut successors: N) -> HashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = HashMap::from([(start.to_owned(), 0)]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((state, steps)) = queue.pop_front() {
+        if cancel::cancelled() {
+            break;
+        }
+        for next in successors(&state) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.to_owned(), steps + 1);
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Dijkstra's search from `start` for the cheapest way to a state
+/// satisfying `goal`, the successors coming with the cost of the step.
+pub fn dijkstra<S, C, I, N, G>(start: S, successors: N, goal: G) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+    G: FnMut(&S) -> bool,
+{
+    astar(start, successors, goal, |_| C::zero())
+}
+
+/// A* search, like `dijkstra` but guided by `heuristic`, an estimate of the
+/// remaining cost that must never exceed the actual one.
+pub fn astar<S, C, I, N, G, H>(
+    start: S,
+    mut successors: N,
+    mut goal: G,
+    mut heuristic: H,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+    G: FnMut(&S) -> bool,
+    H: FnMut(&S) -> C,
+{
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), C::zero(), 0))]);
+    let mut nodes = Nodes::new(start);
+    let mut costs = vec![C::zero()];
+    while let Some(Reverse((_, cost, i))) = queue.pop() {
+        if cancel::cancelled() {
+            break;
+        }
+        if cost > costs[i] {
+            // A cheaper way there was found after this one was queued.
+            continue;
+        }
+        if goal(&nodes.states[i]) {
+            return Some(nodes.path(cost, i));
+        }
+        for (next, step) in successors(&nodes.states[i]) {
+            let next_cost = cost + step;
+            let estimate = next_cost + heuristic(&next);
+            let (j, new) = nodes.insert(next, i);
+            if new {
+                costs.push(next_cost);
+            } else if next_cost < costs[j] {
+                costs[j] = next_cost;
+                nodes.parents[j] = Some(i);
+            } else {
+                continue;
+            }
+            queue.push(Reverse((estimate, next_cost, j)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Steps of 1, 2 or 5, the longer ones costing their length squared.
+    fn steps(&n: &i64) -> Vec<(i64, i64)> {
+        vec![(n + 1, 1), (n + 2, 4), (n + 5, 25)]
+    }
+
+    #[test]
+    fn searches() {
+        let path = bfs(0, |&n| [n + 2, n + 5], |&n| n == 9).unwrap();
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.states, vec![0, 2, 4, 9]);
+        assert_eq!(bfs(0, |&n| (n < 20).then_some(n + 2), |&n| n == 9), None);
+        let distances = reachable(0, |&n| (n < 4).then_some(n + 1));
+        assert_eq!(distances.len(), 5);
+        assert_eq!(distances[&4], 4);
+
+        let path = dijkstra(0, steps, |&n| n == 5).unwrap();
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.states, vec![0, 1, 2, 3, 4, 5]);
+        let path = astar(0, steps, |&n| n == 10, |&n| 10 - n).unwrap();
+        assert_eq!(path.cost, 10);
+        assert_eq!(path.states.len(), 11);
+    }
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use std::io;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// The time the part being timed on this thread has spent parsing so
+    /// far, or nothing if it is not timed, or is parsing right now.
+    static PARSING: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Run `f`, parsing some of the input of the part being solved on this
+/// thread, so that the time it takes is reported as parsing rather than
+/// solving.  Parsing within `f`, such as reading the input, counts once.
+pub fn parsing<R, F: FnOnce() -> R>(f: F) -> R {
+    let Some(spent) = PARSING.take() else {
+        return f();
+    };
+    let start = Instant::now();
+    let rv = f();
+    PARSING.set(Some(spent + start.elapsed()));
+    rv
+}
+
+/// Run `f`, solving a part, and return its result with how much of its time
+/// it spent parsing.
+pub fn timed<R, F: FnOnce() -> R>(f: F) -> (R, Duration) {
+    let previous = PARSING.replace(Some(Duration::ZERO));
+    let rv = f();
+    (rv, PARSING.replace(previous).unwrap_or_default())
+}
+
+/// A reader of a part's input whose reads count as parsing, so that a part
+/// reading its input as it goes is not timed as solving while it waits.
+pub struct Reader<R>(pub R);
+
+impl<R: io::Read> io::Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        parsing(|| self.0.read(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::thread;
+
+    #[test]
+    fn phases() {
+        let pause = |millis| thread::sleep(Duration::from_millis(millis));
+        assert_eq!(parsing(|| 1), 1);
+        let start = Instant::now();
+        let (rv, spent) = timed(|| {
+            parsing(|| pause(20));
+            pause(10);
+            parsing(|| {
+                let mut data = String::new();
+                Reader("input".as_bytes())
+                    .read_to_string(&mut data)
+                    .unwrap();
+                pause(10);
+                data
+            })
+        });
+        let elapsed = start.elapsed();
+        assert_eq!(rv, "input");
+        assert!(spent >= Duration::from_millis(30));
+        assert!(spent + Duration::from_millis(10) <= elapsed);
+        assert_eq!(timed(|| pause(1)).1, Duration::ZERO);
+    }
+}
@@ -0,0 +1,80 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether tracing was ever turned on, so that while it is not, a trace
+/// point costs no more than loading this.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The verbosity of the day being solved on this thread.
+    static LEVEL: Cell<u8> = const { Cell::new(0) };
+    /// The label its traces are prefixed with.
+    static LABEL: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Whether trace points of the given level are shown for the day being
+/// solved on this thread.  By convention 1 is for an occasional summary, 2
+/// for a line per step and 3 for dumps of the whole state.
+#[inline]
+pub fn enabled(level: u8) -> bool {
+    ENABLED.load(Ordering::Relaxed) && LEVEL.with(|current| current.get() >= level)
+}
+
+/// Run `f` with the trace points up to `level` shown, on stderr prefixed by
+/// `label`, as those of the day it solves.
+pub fn with_level<R, F: FnOnce() -> R>(label: &str, level: u8, f: F) -> R {
+    if level > 0 {
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+    let previous = (
+        LEVEL.with(|current| current.replace(level)),
+        LABEL.with(|current| current.replace(label.to_owned())),
+    );
+    let rv = f();
+    LEVEL.with(|current| current.set(previous.0));
+    LABEL.with(|current| current.replace(previous.1));
+    rv
+}
+
+/// Show a trace, which `trace!` has checked is enabled.
+pub fn emit(args: fmt::Arguments) {
+    LABEL.with(|label| {
+        for line in args.to_string().lines() {
+            eprintln!("[{}] {}", label.borrow(), line);
+        }
+    })
+}
+
+/// Show a diagnostic of the given level, formatted as by `format!`, when
+/// tracing of the day being solved is at least that verbose.  Nothing is
+/// formatted, or evaluated, otherwise.
+#[macro_export]
+macro_rules! trace {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::trace::enabled($level) {
+            $crate::trace::emit(format_args!($($arg)+));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert!(!enabled(1));
+        with_level("17", 2, || {
+            assert!(enabled(1) && enabled(2) && !enabled(3));
+            with_level("18", 0, || assert!(!enabled(1)));
+            let mut evaluated = false;
+            trace!(3, "{}", {
+                evaluated = true;
+                0
+            });
+            assert!(!evaluated);
+        });
+        assert!(!enabled(1));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::day::*;
+use crate::grid::Grid;
+use std::cell::RefCell;
+use std::fs;
+use std::io::Write;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// How the frames of a simulation are shown.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// Animated on stderr, replacing the previous frame.
+    Terminal,
+    /// Written to the directory as a numbered series of PPM images, e.g.
+    /// "17-1-00042.ppm" for the 42nd frame of day 17, part 1.
+    Ppm(PathBuf),
+}
+
+/// A mode is written "terminal", "ppm", for images in "frames", or
+/// "ppm:DIR".
+impl FromStr for Mode {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "terminal" => Ok(Self::Terminal),
+            None if s == "ppm" => Ok(Self::Ppm(PathBuf::from("frames"))),
+            Some(("ppm", dir)) if !dir.is_empty() => Ok(Self::Ppm(PathBuf::from(dir))),
+            _ => Err(AocError::new(Reason::Parse).token(s)),
+        }
+    }
+}
+
+/// The time each frame stays on the terminal.
+const FRAME_DELAY: Duration = Duration::from_millis(50);
+
+/// The side, in pixels, of a cell in the images of grids no wider or
+/// taller than the given number of cells.
+const SCALES: [(usize, usize); 3] = [(100, 4), (250, 2), (usize::MAX, 1)];
+
+/// Whether visualisation was ever turned on, so that while it is not, a
+/// frame costs no more than loading this.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct Visualizer {
+    label: String,
+    part: usize,
+    mode: Mode,
+    frames: usize,
+}
+
+thread_local! {
+    /// Where the frames of the part being solved on this thread go.
+    static CURRENT: RefCell<Option<Visualizer>> = const { RefCell::new(None) };
+}
+
+/// Whether frames are shown for the part being solved on this thread, for
+/// simulations that would rather not even keep track of what to render.
+#[inline]
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) && CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// Run `f` with its frames shown as `mode` says, as those of the given day
+/// and part.
+pub fn with_mode<R, F: FnOnce() -> R>(label: &str, part: usize, mode: Option<&Mode>, f: F) -> R {
+    let visualizer = mode.map(|mode| {
+        ENABLED.store(true, Ordering::Relaxed);
+        Visualizer {
+            label: label.to_owned(),
+            part,
+            mode: mode.to_owned(),
+            frames: 0,
+        }
+    });
+    let previous = CURRENT.with(|current| current.replace(visualizer));
+    let rv = f();
+    CURRENT.with(|current| current.replace(previous));
+    rv
+}
+
+/// Show a frame, a picture of the simulation as text, one line per row,
+/// rendered only when frames are shown.  Failing to show it stops showing
+/// frames for the rest of the part, rather than failing the part.
+pub fn frame<F: FnOnce() -> String>(render: F) {
+    if !enabled() {
+        return;
+    }
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        let Some(visualizer) = current.as_mut() else {
+            return;
+        };
+        visualizer.frames += 1;
+        if let Err(e) = visualizer.show(&render()) {
+            eprintln!("cannot show frames of {}: {}", visualizer.label, e);
+            *current = None;
+        }
+    })
+}
+
+impl Visualizer {
+    fn show(&self, text: &str) -> BoxResult<()> {
+        match &self.mode {
+            Mode::Terminal => {
+                let mut stderr = io::stderr().lock();
+                // Home the cursor and clear the screen before drawing.
+                write!(stderr, "\x1b[H\x1b[2J{}", text)?;
+                writeln!(
+                    stderr,
+                    "{} part {} frame {}",
+                    self.label, self.part, self.frames
+                )?;
+                stderr.flush()?;
+                thread::sleep(FRAME_DELAY);
+                Ok(())
+            }
+            Mode::Ppm(dir) => {
+                let name = format!(
+                    "{}-{}-{:05}.ppm",
+                    self.label.replace(':', "-"),
+                    self.part,
+                    self.frames
+                );
+                fs::create_dir_all(dir)?;
+                write_ppm(&dir.join(name), text)
+            }
+        }
+    }
+}
+
+/// The colour of a cell in an image, by the character it is drawn with.
+fn colour(c: char) -> [u8; 3] {
+    match c {
+        ' ' | '.' => [0, 0, 0],
+        '#' => [128, 128, 128],
+        'o' => [230, 200, 100],
+        '+' => [255, 255, 0],
+        '@' => [220, 80, 40],
+        '*' => [200, 220, 255],
+        'E' | 'e' => [80, 220, 80],
+        '>' | 'v' | '<' | '^' => [255, 80, 80],
+        _ => [255, 255, 255],
+    }
+}
+
+/// Write the text as a binary PPM image, each character a square of its
+/// colour, the image scaled down for larger grids.
+fn write_ppm(path: &Path, text: &str) -> BoxResult<()> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let grid = Grid::from_lines(&lines, Some(' '), Some)?;
+    let size = grid.width().max(grid.height());
+    let scale = SCALES
+        .iter()
+        .find(|&&(limit, _)| size <= limit)
+        .map_or(1, |&(_, scale)| scale);
+    let mut image = format!(
+        "P6\n{} {}\n255\n",
+        grid.width() * scale,
+        grid.height() * scale
+    )
+    .into_bytes();
+    for row in grid.rows() {
+        let pixels = row
+            .iter()
+            .flat_map(|&c| iter::repeat_n(colour(c), scale))
+            .flatten()
+            .collect::<Vec<_>>();
+        for _ in 0..scale {
+            image.extend(&pixels);
+        }
+    }
+    fs::write(path, image)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        assert_eq!("terminal".parse::<Mode>().unwrap(), Mode::Terminal);
+        assert_eq!(
+            "ppm:out".parse::<Mode>().unwrap(),
+            Mode::Ppm(PathBuf::from("out"))
+        );
+        assert!("ppm:".parse::<Mode>().is_err());
+        assert!("gif".parse::<Mode>().is_err());
+
+        let dir = std::env::temp_dir().join(format!("aoc-visual-{}", std::process::id()));
+        let mut rendered = 0;
+        frame(|| {
+            rendered += 1;
+            String::new()
+        });
+        assert_eq!(rendered, 0);
+        with_mode("2020:8", 2, Some(&Mode::Ppm(dir.to_owned())), || {
+            assert!(enabled());
+            frame(|| "#.\n.o\n+".to_owned());
+            frame(|| "#".to_owned());
+        });
+        assert!(!enabled());
+        let image = fs::read(dir.join("2020-8-2-00001.ppm")).unwrap();
+        assert!(image.starts_with(b"P6\n8 12\n255\n"));
+        assert_eq!(image.len(), 12 + 8 * 12 * 3);
+        assert_eq!(image[12..15], [128, 128, 128]);
+        assert!(dir.join("2020-8-2-00002.ppm").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
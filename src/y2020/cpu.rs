@@ -1,7 +1,6 @@
 use crate::day::*;
 use std::io;
 
-#[allow(unused)]
 #[derive(Copy, Clone)]
 pub enum Instruction {
     Acc(i64),
@@ -9,7 +8,9 @@ pub enum Instruction {
     Nop(i64),
 }
 
-#[allow(unused)]
+/// The handheld console from 2020: an accumulator machine running acc, jmp
+/// and nop instructions.  In debug mode a run stops before executing any
+/// instruction a second time.
 #[derive(Clone)]
 pub struct Cpu {
     p: Vec<Instruction>,
@@ -18,7 +19,6 @@ pub struct Cpu {
     debug: bool,
 }
 
-#[allow(unused)]
 impl Cpu {
     pub fn from(input: &mut dyn io::Read) -> BoxResult<Self> {
         let cpu = Self {
@@ -27,13 +27,13 @@ impl Cpu {
                 .map(|r| {
                     r.map_err(|e| e.into()).and_then(|s| {
                         let (opcode, arg) =
-                            s.split_ascii_whitespace().collect_tuple().ok_or(AocError)?;
+                            s.split_ascii_whitespace().collect_tuple().with_token(&s)?;
                         let arg = arg.parse()?;
                         match opcode {
                             "acc" => Ok(Instruction::Acc(arg)),
                             "jmp" => Ok(Instruction::Jmp(arg)),
                             "nop" => Ok(Instruction::Nop(arg)),
-                            _ => Err(AocError.into()),
+                            _ => Err(AocError::new(Reason::Parse).token(opcode).into()),
                         }
                     })
                 })
@@ -0,0 +1,105 @@
+use crate::day::*;
+use crate::random::Rng;
+use crate::timing;
+use crate::y2020::cpu::{Cpu, Instruction};
+
+pub struct Day08 {}
+
+type Output = i64;
+
+impl Day for Day08 {
+    fn tag(&self) -> &str {
+        "08"
+    }
+
+    fn part1(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part1_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn part2(&self, input: &dyn Fn() -> Box<dyn io::Read>, _params: &Params) -> Answer {
+        self.part2_impl(&mut *input()).in_day(self.tag()).into()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize, _params: &Params) -> BoxResult<String> {
+        Ok(Self::generate_impl(rng, size))
+    }
+}
+
+impl Day08 {
+    fn part1_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
+        match timing::parsing(|| Cpu::from(input))?.debug(true).run(0)? {
+            (true, a) => Ok(a),
+            _ => Err(AocError::new(Reason::NoSolution))?,
+        }
+    }
+
+    fn part2_impl(&self, input: &mut dyn io::Read) -> BoxResult<Output> {
+        let cpu = timing::parsing(|| Cpu::from(input))?.debug(true);
+        // Swap one jmp for a nop, or vice versa, until the program terminates.
+        let (_, a) = cpu
+            .instruction_index(|i| !matches!(i, Instruction::Acc(_)))
+            .map(|i| {
+                cpu.clone().patch(i, |i| match i {
+                    Instruction::Jmp(x) => Instruction::Nop(x),
+                    Instruction::Nop(x) => Instruction::Jmp(x),
+                    i => i,
+                })
+            })
+            .map(|mut cpu| cpu.run(0))
+            .find(|r| r.as_ref().map_or(true, |(looped, _)| !looped))
+            .with_reason(Reason::NoSolution)??;
+        Ok(a)
+    }
+
+    /// A boot program of about `size` instructions: runs of acc and nop
+    /// each ended by a jmp to the next run to execute, the last one out of
+    /// the program, and in one run a nop corrupted into a jmp back to a run
+    /// already executed, so that the program loops until it is repaired.
+    fn generate_impl(rng: &mut Rng, size: usize) -> String {
+        loop {
+            let mut program = vec![];
+            let mut runs = vec![];
+            while program.len() < size.max(2) {
+                let start = program.len();
+                for _ in 1..rng.range(2..=6) {
+                    program.push(if rng.chance(0.25) {
+                        ("nop", 0)
+                    } else {
+                        ("acc", rng.range(-50..=50))
+                    });
+                }
+                program.push(("jmp", 0));
+                runs.push((start, program.len() - 1));
+            }
+            let end = program.len() as i64;
+            for (i, instruction) in program.iter_mut().enumerate() {
+                if instruction.0 == "nop" {
+                    instruction.1 = rng.range(0..=end) - i as i64;
+                }
+            }
+            rng.shuffle(&mut runs[1..]);
+            let mut corruptible = vec![];
+            for (k, &(start, last)) in runs.iter().enumerate() {
+                corruptible.extend(
+                    (start..last)
+                        .filter(|&i| program[i].0 == "nop" && (k > 0 || i > start))
+                        .map(|i| (i, k)),
+                );
+                let next = runs.get(k + 1).map_or(end, |&(next, _)| next as i64);
+                program[last].1 = next - last as i64;
+            }
+            if corruptible.is_empty() {
+                continue;
+            }
+            // A jmp back to the start of this run, or of one executed before.
+            let &(i, k) = rng.pick(&corruptible);
+            let executed = &runs[..k + usize::from(i > runs[k].0)];
+            let &(target, _) = rng.pick(executed);
+            program[i] = ("jmp", target as i64 - i as i64);
+            return program
+                .iter()
+                .map(|(operation, argument)| format!("{} {:+}\n", operation, argument))
+                .collect();
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Puzzles from Advent of Code 2020, solved on top of the same framework.
+
+pub mod cpu;
+pub mod day08;
@@ -0,0 +1,12 @@
+//! The examples of the puzzles, one test per file under fixtures/.
+
+use adventofcode2022::fixture::Fixture;
+
+fn check(path: &str) {
+    let fixture = Fixture::load(path).unwrap();
+    if let Err(e) = fixture.verify(&adventofcode2022::registry()) {
+        panic!("{}:\n{}", path, e);
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/fixtures.rs"));
@@ -0,0 +1,46 @@
+//! Every day's generated inputs are read and solved by its own parts.
+
+use adventofcode2022::day::*;
+use adventofcode2022::random::Rng;
+
+// Parameters that keep the slower days quick on the small inputs.
+fn overrides(year: usize, day: usize) -> Params {
+    match (year, day) {
+        (2022, 15) => Params::new().with("row", "10").with("max", "20"),
+        (2022, 16) => Params::new().with("minutes", "12"),
+        (2022, 19) => Params::new().with("minutes", "12"),
+        _ => Params::new(),
+    }
+}
+
+#[test]
+fn generators() {
+    let registry = adventofcode2022::registry();
+    for ((year, n), day) in registry.iter() {
+        let params = day.params().overlay(&overrides(year, n));
+        for seed in [1, 2] {
+            let generate = || {
+                let mut rng = Rng::new(seed).fork((year * 100 + n) as u64);
+                day.generate(&mut rng, 8, &params).unwrap()
+            };
+            let text = generate();
+            assert_eq!(generate(), text, "{}:{} seed {}", year, n, seed);
+            let input = || -> Box<dyn io::Read> { Box::new(io::Cursor::new(text.to_owned())) };
+            for (part, answer) in [
+                (1, day.part1(&input, &params.for_part(1))),
+                (2, day.part2(&input, &params.for_part(2))),
+            ] {
+                assert!(
+                    !matches!(answer, Answer::Error { .. }),
+                    "{}:{} part {} seed {}: {:?}\n{}",
+                    year,
+                    n,
+                    part,
+                    seed,
+                    answer,
+                    text
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use adventofcode2022::day::*;
+use adventofcode2022::day13::Value;
+use adventofcode2022::day25::Snafu;
+use adventofcode2022::interval::IntervalSet;
+
+#[test]
+fn registry() {
+    let registry = adventofcode2022::registry();
+    assert_eq!(registry.years(), vec![2020, 2022]);
+    let days = registry
+        .iter()
+        .filter(|&((year, _), _)| year == 2022)
+        .collect::<Vec<_>>();
+    assert_eq!(days.len(), 25);
+    assert!(days
+        .iter()
+        .all(|((_, n), day)| day.tag() == format!("{:02}", n)));
+    let input = || -> Box<dyn io::Read> { Box::new("1000\n2000\n\n3000\n".as_bytes()) };
+    let day = registry.get(2022, 1).unwrap();
+    assert_eq!(day.part1(&input, &Params::new()), Answer::Integer(3000));
+    // The 2020 handheld console runs on the reusable Cpu.
+    let input = || -> Box<dyn io::Read> { Box::new("nop +0\nacc +1\njmp -2\n".as_bytes()) };
+    assert_eq!(
+        registry.get(2020, 8).unwrap().part1(&input, &Params::new()),
+        Answer::Integer(1)
+    );
+}
+
+#[test]
+fn structures() {
+    let a = "[1,[2,[3,[4,[5,6,7]]]],8,9]".parse::<Value>().unwrap();
+    let b = "[1,[2,[3,[4,[5,6,0]]]],8,9]".parse::<Value>().unwrap();
+    assert!(b < a);
+    assert_eq!(a.to_string(), "[1,[2,[3,[4,[5,6,7]]]],8,9]");
+
+    let extents = IntervalSet::from_iter([1..=3, 7..=9, 2..=4]);
+    assert_eq!(extents.iter().collect::<Vec<_>>(), vec![1..=4, 7..=9]);
+    assert_eq!(extents.len(), 7);
+
+    let n = i64::from("1=-0-2".parse::<Snafu>().unwrap());
+    assert_eq!(n, 1747);
+    assert_eq!(Snafu::from(n).to_string(), "1=-0-2");
+}
@@ -0,0 +1,22 @@
+//! The days with reference solutions agree with them on random inputs.
+
+use adventofcode2022::differential;
+
+fn agree(year: usize, n: usize, cases: usize, size: usize) {
+    let registry = adventofcode2022::registry();
+    let day = registry.get(year, n).unwrap();
+    let params = day.params().overlay(&day.reference_params());
+    if let Some(found) = differential::compare(day, &params, None, 0, cases, size).unwrap() {
+        panic!("{}:{} {}", year, n, found);
+    }
+}
+
+#[test]
+fn day16() {
+    agree(2022, 16, 100, 10);
+}
+
+#[test]
+fn day19() {
+    agree(2022, 19, 20, 3);
+}